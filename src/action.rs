@@ -1,28 +1,74 @@
+extern crate rand;
+use rand::Rng;
 
-use crate::actor::Actor;
+use crate::energy::ACTION_COST;
 use crate::game::Direction;
-use crate::game::Level;
+use crate::game::Tile;
+use crate::game::TileType;
+use crate::item::{ItemKind, Slot};
 use crate::math::Vec2i;
+use crate::ecs::Entity;
+use crate::effect;
+use crate::effect::Effect;
+use crate::event::Event;
+use crate::spell;
+use crate::game::Colour;
+use crate::status;
+use crate::systems;
+use crate::trap;
+use crate::world::World;
+
+// How far the actor can throw things
+pub const THROW_RANGE: i32 = 6;
+
+// Thrown weapons hit with this plus their own attack bonus
+const THROWN_ATTACK: u32 = 2;
 
 pub trait Action {
-    fn clone_dyn(&self) -> Box<dyn Action>;
-    fn perform(&self, level: &Level, actor: &mut dyn Actor) -> ActionResult;
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult;
 }
 
+// An action either succeeds, costing the actor energy, fails without using
+// up the turn, or hands over to an alternative action to perform instead
 pub struct ActionResult {
-    pub succeeded: bool
+    pub succeeded: bool,
+    pub alternative: Option<Box<dyn Action>>,
+    pub cost: u32,
+    pub messages: Vec<String>
 }
 
-pub struct NullAction {
+impl ActionResult {
+    pub fn success() -> ActionResult {
+        ActionResult { succeeded: true, alternative: None, cost: ACTION_COST, messages: Vec::new() }
+    }
 
-}
+    pub fn failure() -> ActionResult {
+        ActionResult { succeeded: false, alternative: None, cost: 0, messages: Vec::new() }
+    }
 
-impl Action for NullAction {
-    fn clone_dyn(&self) -> Box<dyn Action> {
-        return Box::new(NullAction{});
+    pub fn alternative(action: Box<dyn Action>) -> ActionResult {
+        ActionResult { succeeded: false, alternative: Some(action), cost: 0, messages: Vec::new() }
     }
-    fn perform(&self, _level: &Level, _actor: &mut dyn Actor) -> ActionResult {
-        ActionResult { succeeded: true }
+
+    pub fn with_cost(mut self, cost: u32) -> ActionResult {
+        self.cost = cost;
+        self
+    }
+
+    pub fn with_message(mut self, message: &str) -> ActionResult {
+        self.messages.push(message.to_string());
+        self
+    }
+}
+
+// Deliberately pass the turn
+pub struct WaitAction {
+
+}
+
+impl Action for WaitAction {
+    fn perform(&self, _world: &mut World, _actor: Entity) -> ActionResult {
+        ActionResult::success()
     }
 }
 
@@ -31,24 +77,428 @@ pub struct WalkAction {
 }
 
 impl Action for WalkAction {
-    fn clone_dyn(&self) -> Box<dyn Action> {
-        return Box::new(WalkAction{direction: self.direction});
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let new_pos = world.position(actor) + self.direction.offset();
+
+        // Bump into hostile actors and trade places with allies
+        if let Some(other) = world.entity_at(new_pos) {
+            if world.is_hostile(actor, other) {
+                return ActionResult::alternative(Box::new(AttackAction{target: new_pos}));
+            }
+            return ActionResult::alternative(Box::new(SwapAction{target: new_pos}));
+        }
+
+        if world.level.can_walk(new_pos) {
+            systems::move_entity(world, actor, new_pos);
+            let mut result = ActionResult::success();
+            result.messages = trap::trigger_at(world, actor, new_pos);
+            return result;
+        }
+
+        if world.level.in_bounds(new_pos) &&
+            world.level[new_pos.y as usize][new_pos.x as usize].tile_type == TileType::_Door {
+            return ActionResult::alternative(Box::new(OpenDoorAction{pos: new_pos}));
+        }
+        if world.level.in_bounds(new_pos) &&
+            world.level[new_pos.y as usize][new_pos.x as usize].tile_type == TileType::_LockedDoor {
+            return ActionResult::alternative(Box::new(UnlockAction{pos: new_pos}));
+        }
+
+        ActionResult::failure().with_message("The way is blocked.")
+    }
+}
+
+pub struct OpenDoorAction {
+    pub pos: Vec2i
+}
+
+impl Action for OpenDoorAction {
+    fn perform(&self, world: &mut World, _actor: Entity) -> ActionResult {
+        let tile = &mut world.level[self.pos.y as usize][self.pos.x as usize];
+        if tile.tile_type != TileType::_Door {
+            return ActionResult::failure();
+        }
+
+        *tile = Tile::new(TileType::_OpenDoor);
+        ActionResult::success()
+            .with_cost(ACTION_COST / 2)
+            .with_message("The door creaks open.")
+    }
+}
+
+// Open a locked door with its key, which stays in the lock
+pub struct UnlockAction {
+    pub pos: Vec2i
+}
+
+impl Action for UnlockAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let key_type = match world.locks.iter().find(|(pos, _)| *pos == self.pos) {
+            Some(&(_, key_type)) => key_type,
+            None => return ActionResult::failure()
+        };
+        let key = world.inventories.get(actor).and_then(|inventory| inventory.items.iter()
+            .find(|&&i| world.items.get(i).is_some_and(|item| item.item_type.name == key_type.name))
+            .cloned());
+        let key = match key {
+            Some(key) => key,
+            None => return ActionResult::failure().with_message("The door is locked.")
+        };
+
+        let name = world.describe(key);
+        if let Some(inventory) = world.inventories.get_mut(actor) {
+            inventory.items.retain(|&i| i != key);
+        }
+        world.despawn(key);
+        world.locks.retain(|(pos, _)| *pos != self.pos);
+        world.level[self.pos.y as usize][self.pos.x as usize] = Tile::new(TileType::_OpenDoor);
+        ActionResult::success().with_message(&format!("You unlock the door with {}.", name))
     }
+}
 
-    fn perform(&self, level: &Level, actor: &mut dyn Actor) -> ActionResult {
-        let old_pos = actor.get_position();
-        let new_pos = match self.direction {
-            Direction::North => old_pos + Vec2i::new(0, -1),
-            Direction::South => old_pos + Vec2i::new(0,  1),
-            Direction::East => old_pos + Vec2i::new( 1,  0),
-            Direction::West => old_pos + Vec2i::new(-1,  0),
+// Melee attack on whoever is standing at the target
+pub struct AttackAction {
+    pub target: Vec2i
+}
+
+impl Action for AttackAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let defender = match world.entity_at(self.target) {
+            Some(defender) => defender,
+            None => return ActionResult::failure()
         };
 
-        if level.can_walk(new_pos) {
-            actor.set_position(new_pos);
-            return ActionResult { succeeded: true };
+        let mut result = ActionResult::success();
+        result.messages = systems::melee_attack(world, actor, defender);
+        result
+    }
+}
+
+// Trade places with an ally standing at the target
+pub struct SwapAction {
+    pub target: Vec2i
+}
+
+impl Action for SwapAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let ally = match world.entity_at(self.target) {
+            Some(ally) => ally,
+            None => return ActionResult::failure()
+        };
+
+        systems::swap_entities(world, actor, ally);
+        ActionResult::success()
+    }
+}
+
+// Spend a turn looking for traps and secret doors nearby
+pub struct SearchAction {
+
+}
+
+impl Action for SearchAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let mut result = ActionResult::success();
+        result.messages = trap::search(world, actor);
+        result
+    }
+}
+
+// Pick up the topmost item lying under the actor
+pub struct PickUpAction {
+
+}
+
+impl Action for PickUpAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let pos = world.position(actor);
+        let item = match world.items_at(pos).last() {
+            Some(&item) => item,
+            None => return ActionResult::failure().with_message("There is nothing here.")
+        };
+
+        let weight = world.items.get(item).map_or(0, |i| i.item_type.weight);
+        let carried = world.carried_weight(actor);
+        let name = world.describe(item);
+        let inventory = match world.inventories.get_mut(actor) {
+            Some(inventory) => inventory,
+            None => return ActionResult::failure()
+        };
+
+        if inventory.items.len() >= inventory.capacity {
+            return ActionResult::failure().with_message("Your pack is full.");
+        }
+        if carried + weight > inventory.max_weight {
+            return ActionResult::failure().with_message(&format!("{} is too heavy to carry.", systems::capitalise(&name)));
+        }
+
+        inventory.items.push(item);
+        world.positions.remove(item);
+        ActionResult::success().with_message(&format!("You pick up {}.", name))
+    }
+}
+
+pub struct DropAction {
+    pub item: Entity
+}
+
+impl Action for DropAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let inventory = match world.inventories.get_mut(actor) {
+            Some(inventory) => inventory,
+            None => return ActionResult::failure()
+        };
+        let index = match inventory.items.iter().position(|&i| i == self.item) {
+            Some(index) => index,
+            None => return ActionResult::failure()
+        };
+
+        inventory.items.remove(index);
+        if let Some(equipment) = world.equipment.get_mut(actor) {
+            equipment.slots.retain(|_, &mut e| e != self.item);
+        }
+        let pos = world.position(actor);
+        world.positions.insert(self.item, pos);
+        ActionResult::success().with_message(&format!("You drop {}.", world.describe(self.item)))
+    }
+}
+
+// Drink a potion or read a scroll from the actor's pack
+pub struct UseItemAction {
+    pub item: Entity
+}
+
+impl Action for UseItemAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let carried = world.inventories.get(actor).is_some_and(|i| i.items.contains(&self.item));
+        if !carried {
+            return ActionResult::failure();
+        }
+        let item_type = match world.items.get(self.item) {
+            Some(item) => item.item_type,
+            None => return ActionResult::failure()
+        };
+        let effect = match item_type.effect {
+            Some(effect) => effect,
+            None => return ActionResult::failure()
+                .with_message(&format!("You can't use {}.", world.describe(self.item)))
+        };
+
+        let verb = if item_type.kind == ItemKind::Scroll { "read" } else { "drink" };
+        let mut result = ActionResult::success()
+            .with_message(&format!("You {} {}.", verb, world.describe(self.item)));
+
+        // Using an item uses it up and reveals what it was
+        if let Some(inventory) = world.inventories.get_mut(actor) {
+            inventory.items.retain(|&i| i != self.item);
+        }
+        world.identify(self.item);
+        world.despawn(self.item);
+
+        result.messages.extend(effect::apply(world, actor, effect));
+        result
+    }
+}
+
+// Wield, wear or put on an item from the pack, swapping out whatever was
+// in the slot before
+pub struct EquipAction {
+    pub item: Entity
+}
+
+impl Action for EquipAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let carried = world.inventories.get(actor).is_some_and(|i| i.items.contains(&self.item));
+        let kind = match world.items.get(self.item) {
+            Some(item) if carried => item.item_type.kind,
+            _ => return ActionResult::failure()
+        };
+        let name = world.describe(self.item);
+        let slots = Slot::for_kind(kind);
+        if slots.is_empty() {
+            return ActionResult::failure().with_message(&format!("You can't equip {}.", name));
+        }
+
+        let equipment = match world.equipment.get_mut(actor) {
+            Some(equipment) => equipment,
+            None => return ActionResult::failure()
+        };
+        if equipment.slot_of(self.item).is_some() {
+            return ActionResult::failure().with_message(&format!("You already have {} equipped.", name));
+        }
+
+        let slot = slots.iter().find(|s| !equipment.slots.contains_key(s)).unwrap_or(&slots[0]);
+        let replaced = equipment.slots.insert(*slot, self.item);
+
+        let mut result = ActionResult::success();
+        if let Some(old) = replaced {
+            result = result.with_message(&format!("You take off {}.", world.describe(old)));
+        }
+        let verb = match kind {
+            ItemKind::Weapon => "wield",
+            ItemKind::Ring => "put on",
+            _ => "wear"
+        };
+        result.with_message(&format!("You {} {}.", verb, name))
+    }
+}
+
+pub struct UnequipAction {
+    pub item: Entity
+}
+
+impl Action for UnequipAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let name = world.describe(self.item);
+        let equipment = match world.equipment.get_mut(actor) {
+            Some(equipment) => equipment,
+            None => return ActionResult::failure()
+        };
+        match equipment.slot_of(self.item) {
+            Some(slot) => {
+                equipment.slots.remove(&slot);
+                ActionResult::success().with_message(&format!("You take off {}.", name))
+            },
+            None => ActionResult::failure().with_message(&format!("You don't have {} equipped.", name))
+        }
+    }
+}
+
+// Shoot the wielded launcher at a target, the arrow stops at the first thing
+// in its way
+pub struct FireAction {
+    pub target: Vec2i
+}
+
+impl Action for FireAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let weapon = match systems::ranged_weapon(world, actor) {
+            Some(weapon) => weapon,
+            None => return ActionResult::failure().with_message("You have nothing to fire.")
+        };
+        let range = world.items.get(weapon).map_or(0, |i| i.item_type.range);
+        let from = world.position(actor);
+        if self.target == from {
+            return ActionResult::failure();
+        }
+
+        let (path, hit) = systems::trace_projectile(world, from, self.target, range);
+        world.events.push(Event::Shot(systems::Projectile { path, colour: Colour::new(200, 170, 120, 255) }));
+
+        let mut result = ActionResult::success();
+        match hit {
+            Some(defender) => {
+                let attack = world.attack(actor);
+                result.messages = systems::ranged_attack(world, actor, defender, attack, "the arrow");
+            },
+            None => result = result.with_message("The arrow hits nothing.")
+        }
+        result
+    }
+}
+
+// Throw an item from the pack at a target. Weapons do damage, potions break
+// and anything else just lands where it stops
+pub struct ThrowAction {
+    pub item: Entity,
+    pub target: Vec2i
+}
+
+impl Action for ThrowAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let carried = world.inventories.get(actor).is_some_and(|i| i.items.contains(&self.item));
+        let item_type = match world.items.get(self.item) {
+            Some(item) if carried => item.item_type,
+            _ => return ActionResult::failure()
+        };
+        let from = world.position(actor);
+        if self.target == from {
+            return ActionResult::failure();
+        }
+
+        if let Some(inventory) = world.inventories.get_mut(actor) {
+            inventory.items.retain(|&i| i != self.item);
+        }
+        if let Some(equipment) = world.equipment.get_mut(actor) {
+            equipment.slots.retain(|_, &mut e| e != self.item);
+        }
+
+        let name = world.describe(self.item);
+        let (path, hit) = systems::trace_projectile(world, from, self.target, THROW_RANGE);
+        let landed = path.last().cloned().unwrap_or(from);
+        world.events.push(Event::Shot(systems::Projectile { path, colour: item_type.colour }));
+
+        let mut result = ActionResult::success().with_message(&format!("You throw {}.", name));
+        if let Some(defender) = hit {
+            let attack = if item_type.kind == ItemKind::Weapon { THROWN_ATTACK + item_type.modifiers.attack.max(0) as u32 } else { 1 };
+            result.messages.extend(systems::ranged_attack(world, actor, defender, attack, &name));
+        }
+
+        if item_type.kind == ItemKind::Potion {
+            result = result.with_message(&format!("{} shatters.", systems::capitalise(&name)));
+            if let (Some(defender), Some(Effect::Inflict(status))) = (hit, item_type.effect) {
+                result.messages.extend(status::inflict(world, defender, status));
+            }
+            world.despawn(self.item);
         } else {
-            return ActionResult { succeeded: false };
+            world.positions.insert(self.item, landed);
+        }
+        result
+    }
+}
+
+// Cast a known spell at a target, paying for it in mana
+pub struct CastAction {
+    pub spell: usize,
+    pub target: Vec2i
+}
+
+impl Action for CastAction {
+    fn perform(&self, world: &mut World, actor: Entity) -> ActionResult {
+        let known = world.spellbooks.get(actor).is_some_and(|b| b.spells.contains(&self.spell));
+        let spell = match world.spells.get(self.spell) {
+            Some(spell) if known => spell.clone(),
+            _ => return ActionResult::failure()
+        };
+        let from = world.position(actor);
+        if self.target == from {
+            return ActionResult::failure();
+        }
+        match world.mana.get_mut(actor) {
+            Some(mana) if mana.current >= spell.mana => mana.current -= spell.mana,
+            _ => return ActionResult::failure().with_message("You don't have enough mana.")
         }
+
+        let (path, area) = spell::shape_cells(world, &spell, from, self.target);
+        let colour = spell.status.map_or(Colour::new(180, 120, 255, 255), |s| s.kind.colour());
+        world.events.push(Event::Shot(systems::Projectile { path, colour }));
+
+        let caster = world.describe(actor);
+        let mut result = ActionResult::success().with_message(&format!("{} {}.",
+            systems::subject_verb(&caster, actor == world.hero, "cast", "casts"), spell.name.to_lowercase()));
+
+        // The caster is never caught in their own spell
+        let targets: Vec<Entity> = area.iter()
+            .filter_map(|&p| world.entity_at(p))
+            .filter(|&e| e != actor)
+            .collect();
+        let missile = format!("the {}", spell.name.to_lowercase());
+        for e in targets {
+            if spell.damage > 0 {
+                let damage = world.rng.gen_range(1, spell.damage + 1);
+                result.messages.extend(systems::deal_damage(world, actor, e, damage, &missile, false));
+            }
+            if let Some(status) = spell.status {
+                if world.is_alive(e) {
+                    result.messages.extend(status::inflict(world, e, status));
+                }
+            }
+        }
+        if let Some(change) = spell.tile {
+            for &p in area.iter() {
+                spell::change_tile(&mut world.level, p, change);
+            }
+        }
+        result
     }
-}
\ No newline at end of file
+}
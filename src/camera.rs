@@ -0,0 +1,55 @@
+use crate::math::Vec2i;
+
+pub const MAX_ZOOM: i32 = 3;
+
+// Which part of the level is on screen. Map cells are drawn zoom screen
+// cells wide, and the view is how many map cells fit
+pub struct Camera {
+    pub zoom: i32,
+    // The map cell in the top left corner of the view
+    origin: Vec2i,
+    view: Vec2i
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera { zoom: 1, origin: Vec2i::new(0, 0), view: Vec2i::new(0, 0) }
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom + 1).min(MAX_ZOOM);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom - 1).max(1);
+    }
+
+    // Centres on the target without showing anything past the edges of the
+    // level. A level smaller than the view sits in the middle of it
+    pub fn follow(&mut self, target: Vec2i, view: Vec2i, level_size: Vec2i) {
+        self.view = view;
+        let axis = |target: i32, view: i32, level: i32| {
+            if level <= view {
+                (level - view) / 2
+            } else {
+                (target - view / 2).max(0).min(level - view)
+            }
+        };
+        self.origin = Vec2i::new(axis(target.x, view.x, level_size.x), axis(target.y, view.y, level_size.y));
+    }
+
+    // Map cell to its cell in the view
+    pub fn to_view(&self, pos: Vec2i) -> Vec2i {
+        pos - self.origin
+    }
+
+    // Screen cell, as the mouse reports it, to the map cell under it
+    pub fn to_world(&self, screen: Vec2i) -> Vec2i {
+        Vec2i::new(screen.x.div_euclid(self.zoom), screen.y.div_euclid(self.zoom)) + self.origin
+    }
+
+    pub fn in_view(&self, pos: Vec2i) -> bool {
+        let p = self.to_view(pos);
+        p.x >= 0 && p.y >= 0 && p.x < self.view.x && p.y < self.view.y
+    }
+}
@@ -0,0 +1,158 @@
+use crate::energy::Energy;
+use crate::ecs::Entity;
+use crate::game::Colour;
+use crate::item::{ItemType, Slot};
+use crate::progression::Perk;
+use crate::status::{Status, StatusKind};
+use crate::trap::TrapKind;
+use std::collections::HashMap;
+
+// Lower layers are drawn first so actors sit on top of anything on the floor
+pub const LAYER_TRAP: u8 = 0;
+pub const LAYER_ITEM: u8 = 1;
+pub const LAYER_ACTOR: u8 = 2;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Renderable {
+    pub glyph: char,
+    pub colour: Colour,
+    pub layer: u8
+}
+
+// Anything with speed takes part in the turn order
+#[derive(Debug, Copy, Clone)]
+pub struct Speed {
+    pub speed: u32,
+    pub energy: Energy
+}
+
+impl Speed {
+    pub fn new(speed: u32) -> Speed {
+        Speed { speed, energy: Energy::new() }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Faction {
+    Hero,
+    Monsters
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Ai {
+    // How close the hero has to be before the monster gives chase
+    pub sight_range: i32,
+    // Turns left running away from the hero
+    pub fear: u32,
+    // Sleeping monsters stay put until something wakes them
+    pub asleep: bool
+}
+
+#[derive(Copy, Clone)]
+pub struct Item {
+    pub item_type: &'static ItemType
+}
+
+// Carried items have no position, they only live in the owner's list
+pub struct Inventory {
+    pub items: Vec<Entity>,
+    pub capacity: usize,
+    pub max_weight: u32
+}
+
+impl Inventory {
+    pub fn new(capacity: usize, max_weight: u32) -> Inventory {
+        Inventory { items: Vec::new(), capacity, max_weight }
+    }
+}
+
+// Equipped items stay in the inventory, this just records where they are worn
+pub struct Equipment {
+    pub slots: HashMap<Slot, Entity>
+}
+
+impl Equipment {
+    pub fn new() -> Equipment {
+        Equipment { slots: HashMap::new() }
+    }
+
+    pub fn slot_of(&self, item: Entity) -> Option<Slot> {
+        self.slots.iter().find(|(_, &e)| e == item).map(|(&slot, _)| slot)
+    }
+}
+
+// Only the hero gains experience, monsters are worth a fixed amount from their breed
+pub struct Progression {
+    pub level: u32,
+    pub experience: u32,
+    pub perks: Vec<Perk>,
+    // Level ups still waiting on a perk to be chosen
+    pub pending_perks: u32
+}
+
+impl Progression {
+    pub fn new() -> Progression {
+        Progression { level: 1, experience: 0, perks: Vec::new(), pending_perks: 0 }
+    }
+}
+
+// Timed conditions, each one wears off on its own
+pub struct Statuses {
+    pub active: Vec<Status>
+}
+
+impl Statuses {
+    pub fn new() -> Statuses {
+        Statuses { active: Vec::new() }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.active.iter().any(|s| s.kind == kind)
+    }
+
+    // Catching the same status again keeps whichever lasts longer
+    pub fn add(&mut self, status: Status) {
+        match self.active.iter_mut().find(|s| s.kind == status.kind) {
+            Some(existing) => existing.turns = existing.turns.max(status.turns),
+            None => self.active.push(status)
+        }
+    }
+
+    pub fn remove(&mut self, kind: StatusKind) -> bool {
+        let count = self.active.len();
+        self.active.retain(|s| s.kind != kind);
+        self.active.len() != count
+    }
+
+    // Returns the statuses that ran out
+    pub fn tick(&mut self) -> Vec<StatusKind> {
+        for status in self.active.iter_mut() {
+            status.turns = status.turns.saturating_sub(1);
+        }
+        let expired = self.active.iter().filter(|s| s.turns == 0).map(|s| s.kind).collect();
+        self.active.retain(|s| s.turns > 0);
+        expired
+    }
+}
+
+pub struct Mana {
+    pub current: u32,
+    pub max: u32
+}
+
+impl Mana {
+    pub fn new(max: u32) -> Mana {
+        Mana { current: max, max }
+    }
+}
+
+// Spells are indices into the world's spell list
+pub struct Spellbook {
+    pub spells: Vec<usize>
+}
+
+// Hidden traps aren't drawn until the hero finds them
+pub struct Trap {
+    pub kind: TrapKind,
+    pub hidden: bool
+}
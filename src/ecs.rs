@@ -0,0 +1,57 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity(pub usize);
+
+// Sparse component storage indexed by entity id
+pub struct Storage<T> {
+    items: Vec<Option<T>>
+}
+
+impl <T> Storage<T> {
+    pub fn new() -> Storage<T> {
+        Storage { items: Vec::new() }
+    }
+
+    pub fn insert(&mut self, e: Entity, value: T) {
+        if e.0 >= self.items.len() {
+            self.items.resize_with(e.0 + 1, || None);
+        }
+        self.items[e.0] = Some(value);
+    }
+
+    pub fn remove(&mut self, e: Entity) -> Option<T> {
+        match self.items.get_mut(e.0) {
+            Some(item) => item.take(),
+            None => None
+        }
+    }
+
+    pub fn get(&self, e: Entity) -> Option<&T> {
+        match self.items.get(e.0) {
+            Some(item) => item.as_ref(),
+            None => None
+        }
+    }
+
+    pub fn get_mut(&mut self, e: Entity) -> Option<&mut T> {
+        match self.items.get_mut(e.0) {
+            Some(item) => item.as_mut(),
+            None => None
+        }
+    }
+
+    pub fn contains(&self, e: Entity) -> bool {
+        self.get(e).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.items.iter().enumerate().filter_map(|(i, item)| item.as_ref().map(|v| (Entity(i), v)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.items.iter_mut().enumerate().filter_map(|(i, item)| item.as_mut().map(|v| (Entity(i), v)))
+    }
+
+    pub fn entities(&self) -> Vec<Entity> {
+        self.iter().map(|(e, _)| e).collect()
+    }
+}
@@ -0,0 +1,92 @@
+use crate::ecs::Entity;
+use crate::status;
+use crate::status::{Status, StatusKind};
+use crate::systems;
+use crate::world::World;
+
+// How far the fear from a scroll reaches
+const FEAR_RADIUS: i32 = 8;
+
+// How far the blast from a scroll of frost reaches
+const FROST_RADIUS: i32 = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Effect {
+    Heal(u32),
+    Teleport,
+    MagicMapping,
+    Fear(u32),
+    Identify,
+    // Afflicts the user
+    Inflict(Status),
+    // Freezes every monster close by
+    Freeze(u32)
+}
+
+// Applies the effect on behalf of the user and describes what happened
+pub fn apply(world: &mut World, user: Entity, effect: Effect) -> Vec<String> {
+    match effect {
+        Effect::Heal(amount) => {
+            if let Some(stats) = world.stats.get_mut(user) {
+                stats.health = (stats.health + amount).min(stats.max_health);
+            }
+            status::cure(world, user, StatusKind::Poisoned);
+            vec!["You feel much better.".to_string()]
+        },
+        Effect::Teleport => {
+            let pos = world.find_free_spawn();
+            systems::move_entity(world, user, pos);
+            vec!["You are yanked through space!".to_string()]
+        },
+        Effect::MagicMapping => {
+            world.level.explore_all();
+            vec!["A map of the level forms in your mind.".to_string()]
+        },
+        Effect::Fear(turns) => {
+            let pos = world.position(user);
+            let mut scared = 0;
+            for (e, ai) in world.ais.iter_mut() {
+                let p = match world.positions.get(e) {
+                    Some(&p) => p,
+                    None => continue
+                };
+                if (p.x - pos.x).abs() + (p.y - pos.y).abs() <= FEAR_RADIUS {
+                    ai.fear = turns;
+                    scared += 1;
+                }
+            }
+            if scared > 0 {
+                vec!["You hear maniacal laughter in the distance.".to_string()]
+            } else {
+                vec!["You hear maniacal laughter, but nothing seems to care.".to_string()]
+            }
+        },
+        Effect::Identify => {
+            let unknown = world.inventories.get(user)
+                .and_then(|inventory| inventory.items.iter()
+                    .find(|&&i| !world.is_identified(i))
+                    .cloned());
+            match unknown {
+                Some(item) => {
+                    let old_name = world.item_name(item);
+                    world.identify(item);
+                    vec![format!("The {} is a {}.", old_name, world.item_name(item).to_lowercase())]
+                },
+                None => vec!["You have nothing left to identify.".to_string()]
+            }
+        },
+        Effect::Inflict(status) => status::inflict(world, user, status),
+        Effect::Freeze(turns) => {
+            let pos = world.position(user);
+            let targets: Vec<Entity> = world.ais.entities().into_iter()
+                .filter(|&e| world.positions.get(e)
+                    .is_some_and(|p| (p.x - pos.x).abs() + (p.y - pos.y).abs() <= FROST_RADIUS))
+                .collect();
+            let mut messages = vec!["A freezing wind blows out from the scroll.".to_string()];
+            for e in targets {
+                messages.extend(status::inflict(world, e, Status { kind: StatusKind::Frozen, turns }));
+            }
+            messages
+        }
+    }
+}
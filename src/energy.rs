@@ -0,0 +1,69 @@
+// Energy needed before an actor can take a turn
+pub const ACTION_COST: u32 = 12;
+
+pub const SLOW_SPEED: u32 = 2;
+pub const NORMAL_SPEED: u32 = 4;
+pub const FAST_SPEED: u32 = 8;
+
+// Every tick an actor gains energy equal to its speed, once it has
+// accumulated ACTION_COST it gets to act and the cost is deducted
+#[derive(Debug, Copy, Clone)]
+pub struct Energy {
+    amount: u32
+}
+
+impl Energy {
+    pub fn new() -> Energy {
+        Energy { amount: 0 }
+    }
+
+    pub fn can_take_turn(&self) -> bool {
+        self.amount >= ACTION_COST
+    }
+
+    // Returns true if the actor now has enough energy to act
+    pub fn gain(&mut self, speed: u32) -> bool {
+        self.amount += speed;
+        self.can_take_turn()
+    }
+
+    pub fn spend(&mut self, cost: u32) {
+        self.amount = self.amount.saturating_sub(cost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts turns the way the game loop hands them out
+    fn turns_taken(speed: u32, ticks: u32) -> u32 {
+        let mut energy = Energy::new();
+        let mut turns = 0;
+        for _ in 0..ticks {
+            if energy.can_take_turn() || energy.gain(speed) {
+                energy.spend(ACTION_COST);
+                turns += 1;
+            }
+        }
+        turns
+    }
+
+    #[test]
+    fn speeds_act_one_two_four() {
+        let slow = turns_taken(SLOW_SPEED, 120);
+        assert_eq!(slow, 20);
+        assert_eq!(turns_taken(NORMAL_SPEED, 120), slow*2);
+        assert_eq!(turns_taken(FAST_SPEED, 120), slow*4);
+    }
+
+    #[test]
+    fn spending_never_goes_negative() {
+        let mut energy = Energy::new();
+        energy.gain(NORMAL_SPEED);
+        energy.spend(ACTION_COST);
+        assert!(!energy.can_take_turn());
+        assert!(!energy.gain(NORMAL_SPEED*2));
+        assert!(energy.gain(NORMAL_SPEED));
+    }
+}
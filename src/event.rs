@@ -0,0 +1,12 @@
+use crate::ecs::Entity;
+use crate::math::Vec2i;
+use crate::systems::Projectile;
+
+// Something that happened in the world worth showing. Actions and systems
+// record these as they go, and the game plays them back frame by frame after
+// the world has already moved on
+pub enum Event {
+    Moved { entity: Entity, from: Vec2i, to: Vec2i },
+    Damaged { entity: Entity, pos: Vec2i, amount: u32 },
+    Shot(Projectile)
+}
@@ -1,15 +1,22 @@
-extern crate sdl2;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-
-use crate::actor::Actor;
-use crate::hero::Hero;
-use crate::math::Vec2i;
-use crate::monster::Monster;
 use crate::action::*;
-use crate::maze::Maze;
+use crate::camera::Camera;
+use crate::ecs::Entity;
+use crate::energy::ACTION_COST;
+use crate::input::Input;
+use crate::item::Slot;
+use crate::math::Vec2i;
+use crate::minimap;
+use crate::monster;
+use crate::presentation::Presentation;
+use crate::progression;
+use crate::progression::Perk;
+use crate::renderer::{Renderer, TileSprite};
+use crate::spell;
+use crate::stats::Stats;
+use crate::status;
+use crate::status::StatusKind;
+use crate::systems;
+use crate::world::World;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Direction {
@@ -19,19 +26,34 @@ pub enum Direction {
     West
 }
 
+impl Direction {
+    pub fn offset(&self) -> Vec2i {
+        match self {
+            Direction::North => Vec2i::new( 0, -1),
+            Direction::South => Vec2i::new( 0,  1),
+            Direction::East => Vec2i::new( 1,  0),
+            Direction::West => Vec2i::new(-1,  0),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TileType {
     _Empty,
     _Floor,
     _Wall,
     _Door,
+    _OpenDoor,
     _Ladder,
     _Pit,
     _Fire,
-    _Ice
+    _Ice,
+    _Water,
+    _SecretDoor,
+    _LockedDoor
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Colour {
     pub r: u8,
     pub g: u8,
@@ -52,19 +74,80 @@ pub struct Tile {
 }
 
 impl Tile {
+    pub fn new(tile_type: TileType) -> Tile {
+        let colour = match tile_type {
+            TileType::_Empty => Colour::new(64, 64, 64, 255),
+            TileType::_Wall => Colour::new(0, 0, 0, 255),
+            TileType::_Door => Colour::new(150, 90, 40, 255),
+            TileType::_OpenDoor => Colour::new(110, 80, 50, 255),
+            TileType::_Ladder => Colour::new(200, 180, 60, 255),
+            TileType::_Pit => Colour::new(20, 20, 20, 255),
+            TileType::_Fire => Colour::new(220, 90, 30, 255),
+            TileType::_Ice => Colour::new(170, 220, 240, 255),
+            TileType::_Water => Colour::new(40, 80, 170, 255),
+            // Passes for the rock around it until found
+            TileType::_SecretDoor => Colour::new(64, 64, 64, 255),
+            TileType::_LockedDoor => Colour::new(120, 60, 30, 255),
+            TileType::_Floor => Colour::new(128, 128, 128, 255)
+        };
+        Tile { tile_type, colour }
+    }
+
     pub fn can_walk(&self) -> bool {
         match self.tile_type {
             TileType::_Wall => false,
             TileType::_Empty => false,
+            TileType::_Door => false,
+            TileType::_SecretDoor => false,
+            TileType::_LockedDoor => false,
             _ => true
         }
     }
+
+    // Closed doors can't be seen or shot through either
+    pub fn blocks_sight(&self) -> bool {
+        !self.can_walk()
+    }
+
+    // What the tile looks like, for tilesets. Secret doors pass for rock
+    pub fn appearance(&self) -> &'static str {
+        match self.tile_type {
+            TileType::_Empty | TileType::_SecretDoor => "rock",
+            TileType::_Wall => "wall",
+            TileType::_Floor => "floor",
+            TileType::_Door => "door",
+            TileType::_OpenDoor => "open door",
+            TileType::_LockedDoor => "locked door",
+            TileType::_Ladder => "ladder",
+            TileType::_Pit => "pit",
+            TileType::_Fire => "fire",
+            TileType::_Ice => "ice",
+            TileType::_Water => "water"
+        }
+    }
+
+    // For frontends that draw tiles as characters
+    pub fn glyph(&self) -> char {
+        match self.tile_type {
+            TileType::_Floor => '.',
+            TileType::_Door | TileType::_LockedDoor => '+',
+            TileType::_OpenDoor => '\'',
+            TileType::_Ladder => '>',
+            TileType::_Pit => '_',
+            TileType::_Fire => '&',
+            TileType::_Ice => ':',
+            TileType::_Water => '~',
+            TileType::_Empty | TileType::_Wall | TileType::_SecretDoor => '#'
+        }
+    }
 }
 
 pub struct Level {
     pub width: usize,
     pub height: usize,
-    grid: Vec<Tile>
+    grid: Vec<Tile>,
+    explored: Vec<bool>,
+    visible: Vec<bool>
 }
 
 impl Level {
@@ -74,16 +157,65 @@ impl Level {
         Level {
             width,
             height,
-            grid: v
+            grid: v,
+            explored: vec!(false; width*height),
+            visible: vec!(false; width*height)
+        }
+    }
+
+    pub fn is_explored(&self, pos: Vec2i) -> bool {
+        self.in_bounds(pos) && self.explored[pos.y as usize*self.width + pos.x as usize]
+    }
+
+    pub fn is_visible(&self, pos: Vec2i) -> bool {
+        self.in_bounds(pos) && self.visible[pos.y as usize*self.width + pos.x as usize]
+    }
+
+    // Visible tiles are remembered as explored
+    pub fn set_visible(&mut self, pos: Vec2i) {
+        if self.in_bounds(pos) {
+            let i = pos.y as usize*self.width + pos.x as usize;
+            self.visible[i] = true;
+            self.explored[i] = true;
         }
     }
 
+    pub fn clear_visible(&mut self) {
+        for v in self.visible.iter_mut() {
+            *v = false;
+        }
+    }
+
+    pub fn explore_all(&mut self) {
+        for (explored, tile) in self.explored.iter_mut().zip(&self.grid) {
+            *explored |= tile.tile_type != TileType::_Empty && tile.tile_type != TileType::_SecretDoor;
+        }
+    }
+
+    pub fn in_bounds(&self, pos: Vec2i) -> bool {
+        pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32
+    }
+
     pub fn can_walk(&self, pos: Vec2i) -> bool {
-        if pos.x < 0 || pos.x >= self.width as i32 { return false };
-        if pos.y < 0 || pos.y >= self.height as i32 { return false };
+        if !self.in_bounds(pos) { return false };
 
         self[pos.y as usize][pos.x as usize].can_walk()
     }
+
+    pub fn blocks_sight(&self, pos: Vec2i) -> bool {
+        if !self.in_bounds(pos) { return true };
+
+        self[pos.y as usize][pos.x as usize].blocks_sight()
+    }
+
+    // Which neighbours are solid, as bits 1 north, 2 east, 4 south, 8 west,
+    // so walls can be drawn joining up
+    pub fn connections(&self, pos: Vec2i) -> u8 {
+        let neighbours = [Direction::North, Direction::East, Direction::South, Direction::West];
+        neighbours.iter().enumerate()
+            .filter(|(_, d)| self.blocks_sight(pos + d.offset()))
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+    }
 }
 
 impl std::ops::Index<usize> for Level {
@@ -116,74 +248,813 @@ impl std::ops::IndexMut<(usize, usize)> for Level {
     }   
 }
 
-pub struct Game<'a> {
-    canvas: Canvas<Window>,
-    pub level: Level,
-    pub monsters: Vec<Monster<'a>>,
-    pub hero: Hero
+// The panel along the bottom of the screen: a line of stats with the latest
+// messages under it
+const LOG_LINES: i32 = 4;
+const HUD_HEIGHT: i32 = LOG_LINES + 1;
+
+// The minimap in the top right corner, in screen cells. It is left out when
+// the screen is too narrow to spare the room
+const MINIMAP_SIZE: Vec2i = Vec2i { x: 13, y: 10 };
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Mode {
+    Playing,
+    Inventory,
+    Drop,
+    Use,
+    Equip,
+    Unequip,
+    CharacterSheet,
+    LevelUp,
+    Throw,
+    Spells,
+    Targeting,
+    Messages,
+    Map
 }
 
-impl <'a> Game<'a> {
-    pub fn new(canvas: Canvas<Window>) -> Game<'a> {
-        let l: Level = Maze::new(49, 37);
-        let hero_pos = Maze::find_spawn(&l);
-        let mut m = Vec::new();
+// What confirming a target in targeting mode does
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Aim {
+    Fire,
+    Throw(Entity),
+    Cast(usize)
+}
 
-        for i in 0..10 {
-            let p = Maze::find_spawn(&l);
-            let g = Monster::goblin(p);
-            m.push(g);
-        }
+pub struct Game {
+    pub world: World,
+    pub mode: Mode,
+    hero_action: Option<Box<dyn Action>>,
+    // Offered on the level up screen
+    perk_choices: Vec<Perk>,
+    // The cell being aimed at and what to do with it
+    target: Vec2i,
+    aim: Aim,
+    presentation: Presentation,
+    pub camera: Camera,
+    // How many lines the message history is scrolled back
+    history_scroll: usize,
+    // Set once the player leaves the game over screen
+    quit: bool
+}
 
+impl Game {
+    pub fn new() -> Game {
+        let world = World::new();
+        let presentation = Presentation::new(world.hero);
         let mut game = Game {
-            canvas,
-            level: l,
-            monsters: m,
-            hero: Hero::new(hero_pos)
+            world,
+            mode: Mode::Playing,
+            hero_action: None,
+            perk_choices: Vec::new(),
+            target: Vec2i::new(0, 0),
+            aim: Aim::Fire,
+            presentation,
+            camera: Camera::new(),
+            history_scroll: 0,
+            quit: false
+        };
+        systems::update_visibility(&mut game.world);
+        game
+    }
+
+    // Queues the hero's action for the input and lets the world catch up.
+    // World time only moves on when the hero commits to an action
+    pub fn handle_input(&mut self, input: Input) {
+        // Only Enter or Esc leaves, so a key held down when the hero died
+        // doesn't skip the news
+        if self.is_over() {
+            if matches!(input, Input::Confirm | Input::Cancel) {
+                self.quit = true;
+            }
+            return;
+        }
+        // The mouse moving only aims. It neither hurries a shot along nor
+        // closes screens the way a key does
+        if let Input::Hover(_) = input {
+            if self.mode == Mode::Targeting {
+                self.targeting_input(input);
+            }
+            return;
+        }
+        // A key pressed while a shot is in the air lands it straight away,
+        // so that what it did shows before anything else happens
+        self.presentation.finish_shots();
+
+        let action = match self.mode {
+            Mode::Playing => self.playing_input(input),
+            Mode::CharacterSheet => { self.mode = Mode::Playing; None },
+            Mode::LevelUp => { self.level_up_input(input); None },
+            Mode::Targeting => self.targeting_input(input),
+            Mode::Spells => { self.spells_input(input); None },
+            Mode::Messages => { self.messages_input(input); None },
+            Mode::Map => { self.mode = Mode::Playing; None },
+            _ => self.inventory_input(input)
+        };
+        if let Some(action) = action {
+            self.hero_action = Some(action);
+            self.update();
+            systems::update_visibility(&mut self.world);
+        }
+        let events = std::mem::take(&mut self.world.events);
+        self.presentation.absorb(events);
+        self.check_level_up();
+    }
+
+    // Brings up the perk choice whenever the hero has a level up to spend
+    fn check_level_up(&mut self) {
+        if self.mode == Mode::LevelUp || self.is_over() {
+            return;
+        }
+        let pending = self.world.progression.get(self.world.hero).map_or(0, |p| p.pending_perks);
+        if pending > 0 {
+            self.perk_choices = progression::roll_perk_choices(&mut self.world, 3);
+            self.mode = Mode::LevelUp;
+        }
+    }
+
+    // Picking a perk takes no time, and there is no way to skip it
+    fn level_up_input(&mut self, input: Input) {
+        let perk = match input {
+            Input::Key(c) if c.is_ascii_lowercase() => match self.perk_choices.get((c as u8 - b'a') as usize) {
+                Some(&perk) => perk,
+                None => return
+            },
+            _ => return
+        };
+        let hero = self.world.hero;
+        progression::choose_perk(&mut self.world, hero, perk);
+        self.world.messages.add(format!("You gain {}.", perk.name().to_lowercase()));
+        self.perk_choices.clear();
+        self.mode = Mode::Playing;
+    }
+
+    fn playing_input(&mut self, input: Input) -> Option<Box<dyn Action>> {
+        match input {
+            Input::North => Some(Box::new(WalkAction{direction: Direction::North})),
+            Input::South => Some(Box::new(WalkAction{direction: Direction::South})),
+            Input::East => Some(Box::new(WalkAction{direction: Direction::East})),
+            Input::West => Some(Box::new(WalkAction{direction: Direction::West})),
+            Input::Wait => Some(Box::new(WaitAction{})),
+            Input::Key('g') | Input::Key(',') => Some(Box::new(PickUpAction{})),
+            Input::Key('i') => { self.mode = Mode::Inventory; None },
+            Input::Key('d') => { self.mode = Mode::Drop; None },
+            Input::Key('u') | Input::Key('q') | Input::Key('r') => { self.mode = Mode::Use; None },
+            Input::Key('w') => { self.mode = Mode::Equip; None },
+            Input::Key('t') => { self.mode = Mode::Unequip; None },
+            Input::Key('c') => { self.mode = Mode::CharacterSheet; None },
+            Input::Key('p') => { self.mode = Mode::Messages; self.history_scroll = 0; None },
+            Input::Key('o') => { self.mode = Mode::Map; None },
+            Input::Key('s') => Some(Box::new(SearchAction{})),
+            Input::Key('v') => { self.mode = Mode::Throw; None },
+            Input::Key('m') => { self.mode = Mode::Spells; None },
+            Input::Key('+') => { self.camera.zoom_in(); None },
+            Input::Key('-') => { self.camera.zoom_out(); None },
+            Input::Key('f') => {
+                if systems::ranged_weapon(&self.world, self.world.hero).is_some() {
+                    self.start_targeting(Aim::Fire);
+                } else {
+                    self.world.messages.add("You have nothing to fire.".to_string());
+                }
+                None
+            },
+            _ => None
+        }
+    }
+
+    // Up and down scroll through old messages, anything else goes back
+    fn messages_input(&mut self, input: Input) {
+        let total = self.world.messages.all().len();
+        match input {
+            Input::North => self.history_scroll = (self.history_scroll + 1).min(total.saturating_sub(1)),
+            Input::South => self.history_scroll = self.history_scroll.saturating_sub(1),
+            _ => self.mode = Mode::Playing
+        }
+    }
+
+    // Letters pick one of the hero's spells to aim
+    fn spells_input(&mut self, input: Input) {
+        let c = match input {
+            Input::Key(c) if c.is_ascii_lowercase() => c,
+            Input::Cancel => { self.mode = Mode::Playing; return },
+            _ => return
+        };
+        let hero = self.world.hero;
+        let spell = match self.world.spellbooks.get(hero).and_then(|b| b.spells.get((c as u8 - b'a') as usize)) {
+            Some(&spell) => spell,
+            None => return
+        };
+        let mana = self.world.mana.get(hero).map_or(0, |m| m.current);
+        if mana < self.world.spells[spell].mana {
+            self.world.messages.add("You don't have enough mana.".to_string());
+            self.mode = Mode::Playing;
+            return;
+        }
+        self.start_targeting(Aim::Cast(spell));
+    }
+
+    // Aims at the closest monster in view to begin with
+    fn start_targeting(&mut self, aim: Aim) {
+        self.aim = aim;
+        self.target = match self.visible_targets().first() {
+            Some(&target) => target,
+            None => self.world.position(self.world.hero)
+        };
+        self.mode = Mode::Targeting;
+    }
+
+    // Positions of the monsters the hero can see, nearest first
+    fn visible_targets(&self) -> Vec<Vec2i> {
+        let hero_pos = self.world.position(self.world.hero);
+        let mut targets: Vec<Vec2i> = self.world.ais.entities().into_iter()
+            .filter_map(|e| self.world.positions.get(e).cloned())
+            .filter(|&p| self.world.level.is_visible(p))
+            .collect();
+        targets.sort_by_key(|p| (p.x - hero_pos.x).pow(2) + (p.y - hero_pos.y).pow(2));
+        targets
+    }
+
+    // Arrows nudge the cursor, tab cycles through monsters and the mouse
+    // aims directly
+    fn targeting_input(&mut self, input: Input) -> Option<Box<dyn Action>> {
+        match input {
+            Input::North => self.move_target(Direction::North),
+            Input::South => self.move_target(Direction::South),
+            Input::East => self.move_target(Direction::East),
+            Input::West => self.move_target(Direction::West),
+            Input::Next => {
+                let targets = self.visible_targets();
+                if !targets.is_empty() {
+                    let next = targets.iter().position(|&t| t == self.target).map_or(0, |i| (i + 1) % targets.len());
+                    self.target = targets[next];
+                }
+            },
+            Input::Hover(cell) if self.world.level.in_bounds(self.camera.to_world(cell)) =>
+                self.target = self.camera.to_world(cell),
+            Input::Click(cell) if self.world.level.in_bounds(self.camera.to_world(cell)) => {
+                self.target = self.camera.to_world(cell);
+                return self.confirm_target();
+            },
+            Input::Confirm | Input::Key('f') | Input::Key('v') | Input::Key('m') => return self.confirm_target(),
+            Input::Cancel => self.mode = Mode::Playing,
+            _ => {}
+        }
+        None
+    }
+
+    fn move_target(&mut self, direction: Direction) {
+        let pos = self.target + direction.offset();
+        if self.world.level.in_bounds(pos) {
+            self.target = pos;
+        }
+    }
+
+    fn confirm_target(&mut self) -> Option<Box<dyn Action>> {
+        if self.target == self.world.position(self.world.hero) {
+            return None;
+        }
+        self.mode = Mode::Playing;
+        match self.aim {
+            Aim::Fire => Some(Box::new(FireAction{target: self.target})),
+            Aim::Throw(item) => Some(Box::new(ThrowAction{item, target: self.target})),
+            Aim::Cast(spell) => Some(Box::new(CastAction{spell, target: self.target}))
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.presentation.is_busy()
+    }
+
+    // Letters pick an item from the hero's pack
+    fn inventory_input(&mut self, input: Input) -> Option<Box<dyn Action>> {
+        match input {
+            Input::Cancel => {
+                self.mode = Mode::Playing;
+                None
+            },
+            Input::Key('i') if self.mode == Mode::Inventory => {
+                self.mode = Mode::Playing;
+                None
+            },
+            Input::Key(c) => {
+                let item = self.inventory_item(c)?;
+                if self.mode == Mode::Throw {
+                    self.start_targeting(Aim::Throw(item));
+                    return None;
+                }
+                let action: Box<dyn Action> = match self.mode {
+                    Mode::Drop => Box::new(DropAction{item}),
+                    Mode::Use => Box::new(UseItemAction{item}),
+                    Mode::Equip => Box::new(EquipAction{item}),
+                    Mode::Unequip => Box::new(UnequipAction{item}),
+                    _ => return None
+                };
+                self.mode = Mode::Playing;
+                Some(action)
+            },
+            _ => None
+        }
+    }
+
+    fn inventory_item(&self, c: char) -> Option<Entity> {
+        if !c.is_ascii_lowercase() {
+            return None;
+        }
+        let index = (c as u8 - b'a') as usize;
+        self.world.inventories.get(self.world.hero)?.items.get(index).cloned()
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.world.is_hero_alive()
+    }
+
+    // The frontends close once this is set
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    // Advances the world until it is the hero's turn and no action is queued
+    fn update(&mut self) {
+        loop {
+            for e in self.world.speeds.entities() {
+                // Skip anyone killed earlier in this pass
+                if !self.world.speeds.contains(e) {
+                    continue;
+                }
+                if !self.take_turn(e) && e == self.world.hero {
+                    return;
+                }
+                if self.is_over() {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Gives the entity energy and lets it act once it has enough.
+    // Returns false if the entity is waiting on input or its action failed
+    fn take_turn(&mut self, e: Entity) -> bool {
+        let gain = self.world.speed(e);
+        let speed = match self.world.speeds.get_mut(e) {
+            Some(speed) => speed,
+            None => return true
+        };
+        if !speed.energy.can_take_turn() && !speed.energy.gain(gain) {
+            return true;
+        }
+
+        // Frozen actors lose their turn but the clock still runs for them
+        if status::has(&self.world, e, StatusKind::Frozen) {
+            if let Some(speed) = self.world.speeds.get_mut(e) {
+                speed.energy.spend(ACTION_COST);
+            }
+            if e == self.world.hero {
+                self.world.turns += 1;
+            }
+            for m in systems::end_turn(&mut self.world, e) {
+                self.world.messages.add(m);
+            }
+            return true;
+        }
+
+        let mut action = if e == self.world.hero {
+            match self.hero_action.take() {
+                Some(action) => action,
+                None => return false
+            }
+        } else {
+            monster::choose_action(&mut self.world, e)
+        };
+
+        // Keep performing alternatives until an action succeeds or fails
+        loop {
+            let result = action.perform(&mut self.world, e);
+            for m in result.messages {
+                self.world.messages.add(m);
+            }
+
+            match result.alternative {
+                Some(alternative) => action = alternative,
+                None => {
+                    if result.succeeded {
+                        if let Some(speed) = self.world.speeds.get_mut(e) {
+                            speed.energy.spend(result.cost);
+                        }
+                        if e == self.world.hero {
+                            self.world.turns += 1;
+                        }
+                        for m in systems::end_turn(&mut self.world, e) {
+                            self.world.messages.add(m);
+                        }
+                    }
+                    return result.succeeded;
+                }
+            }
+        }
+    }
+
+    // Moves the animations along a frame. Called once per frame whether or
+    // not anything is moving
+    pub fn tick(&mut self) {
+        self.presentation.tick();
+    }
+
+    // Frames the view on the hero, then draws the map through it with the
+    // screen for the current mode on top
+    pub fn draw(&mut self, renderer: &mut dyn Renderer) {
+        renderer.clear(Colour::new(100, 100, 100, 255));
+        renderer.set_zoom(self.camera.zoom);
+        let level_size = Vec2i::new(self.world.level.width as i32, self.world.level.height as i32);
+        let screen = renderer.size();
+        let view = Vec2i::new(screen.x / self.camera.zoom, (screen.y - HUD_HEIGHT).max(0) / self.camera.zoom);
+        self.camera.follow(self.world.position(self.world.hero), view, level_size);
+
+        for j in 0..self.world.level.height {
+            for i in 0..self.world.level.width {
+                let pos = Vec2i::new(i as i32, j as i32);
+                if !self.world.level.is_explored(pos) || !self.camera.in_view(pos) {
+                    continue;
+                }
+
+                // Remembered tiles out of sight are drawn dimmed
+                let tile = self.world.level[j][i];
+                let colour = tile.colour;
+                let lit = self.world.level.is_visible(pos);
+                let shift = if lit { 0 } else { 1 };
+                renderer.draw_tile(self.camera.to_view(pos), &TileSprite {
+                    name: tile.appearance(),
+                    glyph: tile.glyph(),
+                    colour: Colour::new(colour.r >> shift, colour.g >> shift, colour.b >> shift, 255),
+                    lit,
+                    connections: self.world.level.connections(pos)
+                });
+            }
+        }
+
+        for (e, pos, renderable) in systems::render_list(&self.world) {
+            if self.camera.in_view(pos) {
+                let at = self.camera.to_view(pos).to_f32() + self.presentation.offset(e, pos);
+                let colour = if self.presentation.is_flashing(e) { Colour::new(255, 255, 255, 255) } else { renderable.colour };
+                renderer.draw_glyph(at, &self.world.item_name(e), renderable.glyph, colour);
+            }
+        }
+        self.presentation.draw(renderer, &self.camera, &self.world.level);
+        self.draw_minimap(renderer);
+        self.draw_hud(renderer);
+
+        match self.mode {
+            _ if self.is_over() => self.draw_game_over(renderer),
+            Mode::Inventory => self.draw_inventory(renderer, "Inventory"),
+            Mode::Drop => self.draw_inventory(renderer, "Drop which item?"),
+            Mode::Use => self.draw_inventory(renderer, "Use which item?"),
+            Mode::Equip => self.draw_inventory(renderer, "Equip which item?"),
+            Mode::Unequip => self.draw_inventory(renderer, "Take off which item?"),
+            Mode::CharacterSheet => self.draw_character_sheet(renderer),
+            Mode::LevelUp => self.draw_level_up(renderer),
+            Mode::Throw => self.draw_inventory(renderer, "Throw which item?"),
+            Mode::Spells => self.draw_spells(renderer),
+            Mode::Targeting => self.draw_targeting(renderer),
+            Mode::Messages => self.draw_message_history(renderer),
+            Mode::Map => self.draw_overview(renderer),
+            Mode::Playing => {}
+        }
+
+        renderer.present();
+    }
+
+    // Outlines the line of fire up to where the shot would stop, and for
+    // spells everything caught in it
+    fn draw_targeting(&self, renderer: &mut dyn Renderer) {
+        let hero = self.world.hero;
+        let from = self.world.position(hero);
+        let (path, area) = match self.aim {
+            Aim::Fire => {
+                let range = systems::ranged_weapon(&self.world, hero)
+                    .and_then(|w| self.world.items.get(w))
+                    .map_or(0, |i| i.item_type.range);
+                (systems::trace_projectile(&self.world, from, self.target, range).0, Vec::new())
+            },
+            Aim::Throw(_) => (systems::trace_projectile(&self.world, from, self.target, THROW_RANGE).0, Vec::new()),
+            Aim::Cast(spell) => spell::shape_cells(&self.world, &self.world.spells[spell], from, self.target)
+        };
+        let hit = path.iter().chain(area.iter())
+            .filter_map(|&p| self.world.entity_at(p))
+            .find(|&e| e != hero);
+
+        for &pos in path.iter().filter(|&&p| self.camera.in_view(p)) {
+            renderer.draw_marker(self.camera.to_view(pos), Colour::new(230, 200, 60, 255));
+        }
+        for &pos in area.iter().filter(|&&p| self.camera.in_view(p)) {
+            renderer.draw_outline(self.camera.to_view(pos), Colour::new(200, 120, 230, 255));
+        }
+        renderer.draw_outline(self.camera.to_view(self.target), Colour::new(230, 60, 60, 255));
+
+        let text = match hit {
+            Some(e) => format!("Target: {}", self.world.describe(e)),
+            None => "Target: nothing".to_string()
+        };
+        Game::draw_panel(renderer, Vec2i::new(1, 1), &[text]);
+    }
+
+    fn draw_spells(&self, renderer: &mut dyn Renderer) {
+        let hero = self.world.hero;
+        let (mana, max_mana) = self.world.mana.get(hero).map_or((0, 0), |m| (m.current, m.max));
+        let mut lines = vec![format!("Cast which spell? ({}/{} mana)", mana, max_mana), String::new()];
+        if let Some(spellbook) = self.world.spellbooks.get(hero) {
+            for (i, &s) in spellbook.spells.iter().enumerate() {
+                let spell = &self.world.spells[s];
+                lines.push(format!("{} - {} ({} mana)", (b'a' + i as u8) as char, spell.name, spell.mana));
+            }
+        }
+
+        Game::draw_panel(renderer, Vec2i::new(2, 2), &lines);
+    }
+
+    // Stats and statuses along the bottom of the screen, with the latest
+    // messages under them fading as they get older
+    fn draw_hud(&self, renderer: &mut dyn Renderer) {
+        let world = &self.world;
+        let hero = world.hero;
+        let screen = renderer.size();
+        let top = screen.y - HUD_HEIGHT;
+        renderer.fill_cells(Vec2i::new(0, top), Vec2i::new(screen.x, HUD_HEIGHT), Colour::new(20, 20, 30, 255));
+
+        let (health, max_health) = world.stats.get(hero).map_or((0, 0), |s| (s.health, s.max_health));
+        let (mana, max_mana) = world.mana.get(hero).map_or((0, 0), |m| (m.current, m.max));
+        let level = world.progression.get(hero).map_or(1, |p| p.level);
+        let stats = format!("HP {}/{}  MP {}/{}  Lvl {}  Depth {}  Turn {}",
+            health, max_health, mana, max_mana, level, world.depth, world.turns);
+        let health_colour = if health*4 <= max_health {
+            Colour::new(230, 60, 60, 255)
+        } else {
+            Colour::new(230, 230, 230, 255)
         };
+        renderer.draw_text(Vec2i::new(1, top), &stats, health_colour);
+
+        let mut x = stats.len() as i32 + 3;
+        if let Some(statuses) = world.statuses.get(hero) {
+            for status in statuses.active.iter() {
+                let text = format!("{} {}", status.kind.name(), status.turns);
+                renderer.draw_text(Vec2i::new(x, top), &text, status.kind.colour());
+                x += text.len() as i32 + 2;
+            }
+        }
 
-        return game;
+        let messages = world.messages.recent(LOG_LINES as usize);
+        let oldest = LOG_LINES as usize - messages.len();
+        for (j, message) in messages.iter().enumerate() {
+            let shade = 110 + 40*(oldest + j) as u8;
+            renderer.draw_text(Vec2i::new(1, top + 1 + (oldest + j) as i32), message, Colour::new(shade, shade, shade, 255));
+        }
+    }
+
+    fn draw_minimap(&self, renderer: &mut dyn Renderer) {
+        let screen = renderer.size();
+        if screen.x < MINIMAP_SIZE.x*3 || screen.y - HUD_HEIGHT < MINIMAP_SIZE.y*2 {
+            return;
+        }
+        let pos = Vec2i::new(screen.x - MINIMAP_SIZE.x - 1, 1);
+        renderer.fill_cells(pos, MINIMAP_SIZE, Colour::new(20, 20, 30, 255));
+        renderer.draw_map(pos, MINIMAP_SIZE, &minimap::build(&self.world));
     }
 
-    pub fn update(&mut self) {
-        let action = self.hero.get_action();
-        action.perform(&self.level, &mut self.hero);
-        self.hero.set_next_action(Box::new(NullAction{}));
+    // The whole level as big as the screen allows
+    fn draw_overview(&self, renderer: &mut dyn Renderer) {
+        let screen = renderer.size();
+        renderer.fill_cells(Vec2i::new(0, 0), screen, Colour::new(20, 20, 30, 255));
+        let title = format!("Map of depth {}", self.world.depth);
+        renderer.draw_text(Vec2i::new(1, 1), &title, Colour::new(230, 230, 230, 255));
+        let size = Vec2i::new(screen.x - 2, screen.y - 4);
+        renderer.draw_map(Vec2i::new(1, 3), size, &minimap::build(&self.world));
     }
 
-    pub fn draw(&mut self) {
-        self.canvas.set_draw_color(Color::RGB(100, 100, 100));
-        self.canvas.clear();
+    // Every message so far, newest at the bottom
+    fn draw_message_history(&self, renderer: &mut dyn Renderer) {
+        let screen = renderer.size();
+        renderer.fill_cells(Vec2i::new(0, 0), screen, Colour::new(20, 20, 30, 255));
+        renderer.draw_text(Vec2i::new(1, 1), "Messages (up and down to scroll)", Colour::new(230, 230, 230, 255));
 
-        for j in 0..self.level.height {
-            for i in 0..self.level.width {
-                self.canvas.set_draw_color(Color::RGB(
-                    self.level[j][i].colour.r,
-                    self.level[j][i].colour.g,
-                    self.level[j][i].colour.b));
-                let _result = self.canvas.fill_rect(Rect::new((i*16+1) as i32, (j*16+1) as i32, 14, 14));
+        let rows = (screen.y - 3).max(0) as usize;
+        let messages = self.world.messages.all();
+        let end = messages.len() - self.history_scroll.min(messages.len());
+        let start = end.saturating_sub(rows);
+        for (j, message) in messages[start..end].iter().enumerate() {
+            renderer.draw_text(Vec2i::new(1, 3 + j as i32), message, Colour::new(200, 200, 200, 255));
+        }
+    }
+
+    fn draw_inventory(&self, renderer: &mut dyn Renderer, title: &str) {
+        let hero = self.world.hero;
+        let (items, max_weight) = match self.world.inventories.get(hero) {
+            Some(inventory) => (inventory.items.clone(), inventory.max_weight),
+            None => (Vec::new(), 0)
+        };
+
+        let mut lines = vec![
+            format!("{} ({}/{} lb)", title, self.world.carried_weight(hero), max_weight),
+            String::new()
+        ];
+        if items.is_empty() {
+            lines.push("Your pack is empty.".to_string());
+        }
+        for (i, &item) in items.iter().enumerate() {
+            let name = self.world.item_name(item);
+            let weight = self.world.items.get(item).map_or(0, |i| i.item_type.weight);
+            let slot = self.world.equipment.get(hero).and_then(|e| e.slot_of(item));
+            let worn = match slot {
+                Some(slot) => format!(" [{}]", slot.name()),
+                None => String::new()
+            };
+            lines.push(format!("{} - {} ({} lb){}", (b'a' + i as u8) as char, name, weight, worn));
+        }
+
+        Game::draw_panel(renderer, Vec2i::new(2, 2), &lines);
+    }
+
+    fn draw_character_sheet(&self, renderer: &mut dyn Renderer) {
+        let world = &self.world;
+        let hero = world.hero;
+        let stats = world.stats.get(hero).cloned().unwrap_or(Stats::new(0, 0, 0));
+        let modifiers = world.modifiers(hero);
+        let mana = world.mana.get(hero).map_or((0, 0), |m| (m.current, m.max));
+
+        let (level, experience, to_next, perks) = match world.progression.get(hero) {
+            Some(p) => (p.level, p.experience, progression::experience_to_next(p.level), p.perks.clone()),
+            None => (1, 0, 0, Vec::new())
+        };
+
+        let mut lines = vec![
+            "Character".to_string(),
+            String::new(),
+            format!("Depth           {}", world.depth),
+            format!("Level           {}", level),
+            format!("Experience      {}/{}", experience, to_next),
+            format!("Health          {}/{}", stats.health, stats.max_health),
+            format!("Mana            {}/{}", mana.0, mana.1),
+            format!("Attack          {} ({:+})", world.attack(hero), modifiers.attack),
+            format!("Defence         {} ({:+})", world.defence(hero), modifiers.defence),
+            format!("Speed           {} ({:+})", world.speed(hero), modifiers.speed),
+            format!("Light radius    {} ({:+})", systems::light_radius(world, hero), modifiers.light_radius),
+            format!("Fire resistance {}%", modifiers.fire_resistance.clamp(0, 100)),
+            String::new()
+        ];
+        if let Some(statuses) = world.statuses.get(hero) {
+            for status in statuses.active.iter() {
+                lines.push(format!("{:<15} {} turns", status.kind.name(), status.turns));
+            }
+            if !statuses.active.is_empty() {
+                lines.push(String::new());
             }
         }
+        for slot in Slot::ALL.iter() {
+            let item = world.equipment.get(hero).and_then(|e| e.slots.get(slot).cloned());
+            let name = match item {
+                Some(item) => world.item_name(item),
+                None => "-".to_string()
+            };
+            lines.push(format!("{:<15} {}", slot.name(), name));
+        }
+        if !perks.is_empty() {
+            lines.push(String::new());
+            let names: Vec<&str> = perks.iter().map(|p| p.name()).collect();
+            lines.push(format!("Perks           {}", names.join(", ")));
+        }
 
-        Game::draw_object(&mut self.canvas, &self.hero);
+        Game::draw_panel(renderer, Vec2i::new(2, 2), &lines);
+    }
 
-        for m in &self.monsters {
-            Game::draw_object(&mut self.canvas, m);
+    fn draw_level_up(&self, renderer: &mut dyn Renderer) {
+        let level = self.world.progression.get(self.world.hero).map_or(1, |p| p.level);
+        let mut lines = vec![
+            format!("You reached level {}!", level),
+            String::new(),
+            "Choose a perk:".to_string()
+        ];
+        for (i, perk) in self.perk_choices.iter().enumerate() {
+            lines.push(format!("{} - {} ({})", (b'a' + i as u8) as char, perk.name(), perk.description()));
         }
-        
-        self.canvas.present();
 
+        Game::draw_panel(renderer, Vec2i::new(2, 2), &lines);
     }
 
-    fn draw_object(canvas: &mut Canvas<Window>, actor: &dyn Actor) {
-        let drawable = actor.get_drawable();
-        let colour = drawable.colour;
-        let pos = drawable.pos;
+    // Centred over the map, with the log still showing what happened below
+    fn draw_game_over(&self, renderer: &mut dyn Renderer) {
+        let world = &self.world;
+        let level = world.progression.get(world.hero).map_or(1, |p| p.level);
+        let lines = vec![
+            "You have died.".to_string(),
+            String::new(),
+            format!("Depth {}  Level {}  Turn {}", world.depth, level, world.turns),
+            String::new(),
+            "Press Enter or Esc to quit".to_string()
+        ];
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32 + 2;
+        let height = lines.len() as i32 + 2;
+        let screen = renderer.size();
+        let pos = Vec2i::new((screen.x - width).max(0) / 2, (screen.y - HUD_HEIGHT - height).max(0) / 2);
+        Game::draw_panel(renderer, pos, &lines);
+    }
+
+    // A box of text lines, positions are in cells
+    fn draw_panel(renderer: &mut dyn Renderer, pos: Vec2i, lines: &[String]) {
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32 + 2;
+        let height = lines.len() as i32 + 2;
+
+        renderer.fill_cells(pos, Vec2i::new(width, height), Colour::new(20, 20, 30, 255));
+        for (j, line) in lines.iter().enumerate() {
+            renderer.draw_text(pos + Vec2i::new(1, 1 + j as i32), line, Colour::new(230, 230, 230, 255));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use crate::headless::{Bot, InputSource, NullRenderer};
+    use crate::systems::Projectile;
+
+    fn step(game: &mut Game, renderer: &mut NullRenderer, input: Input) {
+        game.handle_input(input);
+        game.tick();
+        while game.is_animating() {
+            game.tick();
+        }
+        game.draw(renderer);
+    }
+
+    #[test]
+    fn plays_without_a_display() {
+        let mut game = Game::new();
+        let mut renderer = NullRenderer {};
+        let mut bot = Bot::new();
+        let mut inputs = 0;
+        while game.world.turns < 100 && !game.is_over() && inputs < 10_000 {
+            let input = bot.next_input(&game).unwrap_or(Input::Wait);
+            step(&mut game, &mut renderer, input);
+            inputs += 1;
+        }
+        assert!(game.world.turns >= 20, "the game stopped after {} turns", game.world.turns);
+    }
+
+    // Every screen opens, draws and closes again without taking a turn
+    #[test]
+    fn screens_open_and_close() {
+        let mut game = Game::new();
+        let mut renderer = NullRenderer {};
+        for &key in &['i', 'd', 'u', 'w', 't', 'c', 'p', 'o', 'v', 'm'] {
+            step(&mut game, &mut renderer, Input::Key(key));
+            assert_ne!(game.mode, Mode::Playing, "'{}' opened nothing", key);
+            step(&mut game, &mut renderer, Input::Cancel);
+            assert_eq!(game.mode, Mode::Playing, "'{}' didn't close", key);
+        }
+        assert_eq!(game.world.turns, 0);
+    }
+
+    #[test]
+    fn only_enter_or_esc_leaves_the_game_over_screen() {
+        let mut game = Game::new();
+        let mut renderer = NullRenderer {};
+        let hero = game.world.hero;
+        systems::damage_entity(&mut game.world, hero, 1000);
+        assert!(game.is_over());
+
+        step(&mut game, &mut renderer, Input::North);
+        step(&mut game, &mut renderer, Input::Key('i'));
+        assert!(!game.has_quit());
+        step(&mut game, &mut renderer, Input::Confirm);
+        assert!(game.has_quit());
+    }
+
+    // Keys pressed while a shot is still flying act once it lands
+    #[test]
+    fn input_during_a_shot_is_kept() {
+        let mut game = Game::new();
+        let from = game.world.position(game.world.hero);
+        let path = (1..=5).map(|i| from + Vec2i::new(i, 0)).collect();
+        game.world.events.push(Event::Shot(Projectile { path, colour: Colour::new(255, 0, 0, 255) }));
+        game.handle_input(Input::Key('c'));
+        assert!(game.is_animating());
+        game.tick();
+
+        game.handle_input(Input::Cancel);
+        assert!(!game.is_animating());
+        assert_eq!(game.mode, Mode::Playing);
+    }
 
-        canvas.set_draw_color(
-            Color::RGBA(colour.r, colour.g, colour.b, colour.a));
+    // Moving the mouse lets the shot fly on and leaves the screen open
+    #[test]
+    fn hovering_during_a_shot_waits_for_it() {
+        let mut game = Game::new();
+        let from = game.world.position(game.world.hero);
+        let path = (1..=5).map(|i| from + Vec2i::new(i, 0)).collect();
+        game.world.events.push(Event::Shot(Projectile { path, colour: Colour::new(255, 0, 0, 255) }));
+        game.handle_input(Input::Key('c'));
+        game.tick();
 
-        let _result = canvas.fill_rect(
-            Rect::new(pos.x*16+1, pos.y*16+1, 14, 14));
+        game.handle_input(Input::Hover(Vec2i::new(3, 3)));
+        assert!(game.is_animating());
+        assert_eq!(game.mode, Mode::CharacterSheet);
     }
 }
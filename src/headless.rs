@@ -0,0 +1,234 @@
+extern crate rand;
+use rand::Rng;
+
+use crate::game::{Colour, Direction, Game, Mode, TileType};
+use crate::input::Input;
+use crate::math::{Vec2f, Vec2i};
+use crate::minimap::MapImage;
+use crate::renderer::{Renderer, TileSprite};
+
+// Inputs in a row the game can ignore before the run is given up as stuck
+const MAX_IDLE_INPUTS: u32 = 1000;
+
+// Draws nothing, for running without a display
+pub struct NullRenderer {}
+
+impl Renderer for NullRenderer {
+    fn clear(&mut self, _colour: Colour) {}
+    fn size(&self) -> Vec2i { Vec2i::new(80, 40) }
+    fn set_zoom(&mut self, _zoom: i32) {}
+    fn draw_tile(&mut self, _pos: Vec2i, _tile: &TileSprite) {}
+    fn draw_glyph(&mut self, _pos: Vec2f, _name: &str, _glyph: char, _colour: Colour) {}
+    fn draw_label(&mut self, _pos: Vec2f, _text: &str, _colour: Colour) {}
+    fn draw_text(&mut self, _pos: Vec2i, _text: &str, _colour: Colour) {}
+    fn fill_cells(&mut self, _pos: Vec2i, _size: Vec2i, _colour: Colour) {}
+    fn draw_outline(&mut self, _pos: Vec2i, _colour: Colour) {}
+    fn draw_marker(&mut self, _pos: Vec2i, _colour: Colour) {}
+    fn draw_map(&mut self, _pos: Vec2i, _size: Vec2i, _map: &MapImage) {}
+    fn present(&mut self) {}
+}
+
+// Where headless input comes from
+pub trait InputSource {
+    // None once there is nothing left to play
+    fn next_input(&mut self, game: &Game) -> Option<Input>;
+}
+
+// Plays through a list of inputs from a file. Commands are separated by
+// whitespace: north, south, east, west, wait, cancel, confirm, next, or a
+// single letter for anything else
+pub struct Script {
+    inputs: Vec<Input>,
+    next: usize
+}
+
+impl Script {
+    pub fn load(path: &str) -> Result<Script, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+        let mut inputs = Vec::new();
+        for word in text.split_whitespace() {
+            let input = match word {
+                "north" => Input::North,
+                "south" => Input::South,
+                "east" => Input::East,
+                "west" => Input::West,
+                "wait" => Input::Wait,
+                "cancel" => Input::Cancel,
+                "confirm" => Input::Confirm,
+                "next" => Input::Next,
+                _ if word.chars().count() == 1 => Input::Key(word.chars().next().unwrap_or(' ')),
+                _ => return Err(format!("Unknown command in {}: {}", path, word))
+            };
+            inputs.push(input);
+        }
+        Ok(Script { inputs, next: 0 })
+    }
+}
+
+impl InputSource for Script {
+    fn next_input(&mut self, _game: &Game) -> Option<Input> {
+        let input = self.inputs.get(self.next).copied();
+        self.next += 1;
+        input
+    }
+}
+
+// Plays by itself: fights whatever is next to it, picks things up and
+// otherwise wanders about the level
+pub struct Bot {
+    heading: Direction
+}
+
+impl Bot {
+    pub fn new() -> Bot {
+        Bot { heading: Direction::North }
+    }
+}
+
+impl InputSource for Bot {
+    fn next_input(&mut self, game: &Game) -> Option<Input> {
+        match game.mode {
+            Mode::Playing => {},
+            Mode::LevelUp => return Some(Input::Key('a')),
+            _ => return Some(Input::Cancel)
+        }
+
+        let world = &game.world;
+        let pos = world.position(world.hero);
+        let directions = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+        for &d in directions.iter() {
+            if let Some(other) = world.entity_at(pos + d.offset()) {
+                if world.is_hostile(world.hero, other) {
+                    return Some(direction_input(d));
+                }
+            }
+        }
+
+        let room_in_pack = world.inventories.get(world.hero).is_some_and(|i| i.items.len() < i.capacity);
+        if room_in_pack && !world.items_at(pos).is_empty() {
+            return Some(Input::Key('g'));
+        }
+
+        // Keep going the same way until something is in the way, doors
+        // count as open since walking into them opens them
+        let open = |d: Direction| {
+            let p = pos + d.offset();
+            world.level.can_walk(p) ||
+                world.level.in_bounds(p) && world.level[p.y as usize][p.x as usize].tile_type == TileType::_Door
+        };
+        let mut rng = rand::thread_rng();
+        if !open(self.heading) || rng.gen_range(0, 8) == 0 {
+            let options: Vec<Direction> = directions.iter().cloned().filter(|&d| open(d)).collect();
+            if options.is_empty() {
+                return Some(Input::Wait);
+            }
+            self.heading = options[rng.gen_range(0, options.len())];
+        }
+        Some(direction_input(self.heading))
+    }
+}
+
+fn direction_input(direction: Direction) -> Input {
+    match direction {
+        Direction::North => Input::North,
+        Direction::South => Input::South,
+        Direction::East => Input::East,
+        Direction::West => Input::West
+    }
+}
+
+// Runs the game without a window for a number of hero turns, driven by the
+// bot or by a script, then prints how it went
+pub fn run(args: &[String]) {
+    let mut turns = 1000;
+    let mut script = None;
+    let mut verbose = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--turns" => {
+                i += 1;
+                match args.get(i).and_then(|a| a.parse().ok()) {
+                    Some(n) => turns = n,
+                    None => { eprintln!("--turns needs a number"); return; }
+                }
+            },
+            "--script" => {
+                i += 1;
+                match args.get(i) {
+                    Some(path) => script = Some(path.clone()),
+                    None => { eprintln!("--script needs a file"); return; }
+                }
+            },
+            "--verbose" => verbose = true,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let mut source: Box<dyn InputSource> = match script {
+        Some(path) => match Script::load(&path) {
+            Ok(script) => Box::new(script),
+            Err(e) => { eprintln!("{}", e); return; }
+        },
+        None => Box::new(Bot::new())
+    };
+
+    let mut game = Game::new();
+    let mut renderer = NullRenderer {};
+    let mut idle = 0;
+    while game.world.turns < turns && !game.is_over() && idle < MAX_IDLE_INPUTS {
+        let input = match source.next_input(&game) {
+            Some(input) => input,
+            None => break
+        };
+
+        let before = game.world.turns;
+        game.handle_input(input);
+        game.tick();
+        while game.is_animating() {
+            game.tick();
+        }
+        game.draw(&mut renderer);
+
+        idle = if game.world.turns == before { idle + 1 } else { 0 };
+
+        let messages = game.world.messages.take_new();
+        if verbose {
+            for m in messages {
+                println!("{}", m);
+            }
+        }
+    }
+
+    print_summary(&game, idle >= MAX_IDLE_INPUTS);
+}
+
+fn print_summary(game: &Game, stuck: bool) {
+    let world = &game.world;
+    let hero = world.hero;
+
+    let outcome = if game.is_over() {
+        "died"
+    } else if stuck {
+        "stuck"
+    } else {
+        "alive"
+    };
+    println!("Turns:    {}", world.turns);
+    println!("Outcome:  {}", outcome);
+    println!("Depth:    {}", world.depth);
+    if let Some(p) = world.progression.get(hero) {
+        println!("Level:    {} ({} xp)", p.level, p.experience);
+    }
+    if let Some(s) = world.stats.get(hero) {
+        println!("Health:   {}/{}", s.health, s.max_health);
+    }
+    let monsters = world.ais.entities().iter().filter(|&&e| world.is_alive(e)).count();
+    println!("Monsters: {}", monsters);
+    if let Some(inventory) = world.inventories.get(hero) {
+        let items: Vec<String> = inventory.items.iter().map(|&i| world.item_name(i)).collect();
+        println!("Carrying: {}", if items.is_empty() { "nothing".to_string() } else { items.join(", ") });
+    }
+}
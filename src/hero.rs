@@ -1,45 +1,33 @@
-
-use crate::actor::Actor;
-use crate::action::Action;
-use crate::action::NullAction;
-use crate::drawable::Drawable;
+use crate::components::*;
+use crate::ecs::Entity;
+use crate::energy::NORMAL_SPEED;
 use crate::game::Colour;
 use crate::math::Vec2i;
+use crate::spell;
+use crate::stats::Stats;
+use crate::world::World;
 
-pub struct Hero {
-    drawable: Drawable,
-    next_action: Box<dyn Action>
-}
-
-impl Actor for Hero {
-    fn get_drawable(&self) -> Drawable {
-        self.drawable
-    }
-    fn get_position(&self) -> Vec2i {
-        self.drawable.pos
-    }
+// The hero starts out knowing these
+const HERO_SPELLS: [&str; 4] = ["Magic missile", "Fireball", "Cone of cold", "Lightning"];
 
-    fn set_position(&mut self, pos: Vec2i) {
-        self.drawable.pos = pos;
-    }
-
-    fn get_action(&self) -> Box<dyn Action> {
-        self.next_action.clone_dyn()
-    }
+pub fn spawn(world: &mut World, pos: Vec2i) -> Entity {
+    let e = world.spawn();
+    world.positions.insert(e, pos);
+    world.occupancy.add(pos, e);
+    world.renderables.insert(e, Renderable {
+        glyph: '@',
+        colour: Colour{r:255, g:128, b:128, a:255},
+        layer: LAYER_ACTOR
+    });
+    world.names.insert(e, "Hero".to_string());
+    world.stats.insert(e, Stats::new(30, 5, 2));
+    world.speeds.insert(e, Speed::new(NORMAL_SPEED));
+    world.factions.insert(e, Faction::Hero);
+    world.inventories.insert(e, Inventory::new(26, 60));
+    world.equipment.insert(e, Equipment::new());
+    world.progression.insert(e, Progression::new());
+    world.mana.insert(e, Mana::new(20));
+    let spells = HERO_SPELLS.iter().filter_map(|name| spell::find(world, name)).collect();
+    world.spellbooks.insert(e, Spellbook { spells });
+    e
 }
-
-impl Hero {
-    pub fn new((x, y): (i32, i32)) -> Hero {
-        Hero {
-            drawable: Drawable {
-                pos: Vec2i{x:x, y:y},
-                colour: Colour{r:255, g:128, b:128, a:255}
-            },
-            next_action: Box::new(NullAction{})
-        }
-    }
-
-    pub fn set_next_action(&mut self, action: Box<dyn Action>) {
-        self.next_action = action;
-    }
-}
\ No newline at end of file
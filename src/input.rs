@@ -1,7 +1,17 @@
+use crate::math::Vec2i;
 
+#[derive(Debug, Copy, Clone)]
 pub enum Input {
     North,
     South,
     East,
-    West
-}
\ No newline at end of file
+    West,
+    Wait,
+    Cancel,
+    Confirm,
+    Next,
+    Key(char),
+    // The mouse moving over or clicking on a cell
+    Hover(Vec2i),
+    Click(Vec2i)
+}
@@ -0,0 +1,341 @@
+extern crate rand;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+
+use crate::components::*;
+use crate::effect::Effect;
+use crate::ecs::Entity;
+use crate::game::Colour;
+use crate::math::Vec2i;
+use crate::stats::{Modifiers, NO_MODIFIERS};
+use crate::status::{Status, StatusKind};
+use crate::world::World;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ItemKind {
+    Weapon,
+    Armour,
+    Treasure,
+    Potion,
+    Scroll,
+    Ring,
+    Key
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Slot {
+    Weapon,
+    Body,
+    LeftRing,
+    RightRing
+}
+
+impl Slot {
+    pub const ALL: [Slot; 4] = [Slot::Weapon, Slot::Body, Slot::LeftRing, Slot::RightRing];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Slot::Weapon => "Weapon",
+            Slot::Body => "Body",
+            Slot::LeftRing => "Left ring",
+            Slot::RightRing => "Right ring"
+        }
+    }
+
+    // Which slots an item of the given kind can go in, in order of preference
+    pub fn for_kind(kind: ItemKind) -> &'static [Slot] {
+        match kind {
+            ItemKind::Weapon => &[Slot::Weapon],
+            ItemKind::Armour => &[Slot::Body],
+            ItemKind::Ring => &[Slot::LeftRing, Slot::RightRing],
+            _ => &[]
+        }
+    }
+}
+
+pub struct ItemType {
+    pub name: &'static str,
+    pub glyph: char,
+    pub colour: Colour,
+    pub weight: u32,
+    pub kind: ItemKind,
+    pub effect: Option<Effect>,
+    pub modifiers: Modifiers,
+    // How far a wielded launcher can shoot, zero for everything else
+    pub range: i32
+}
+
+pub static DAGGER: ItemType = ItemType {
+    name: "Dagger",
+    glyph: ')',
+    colour: Colour{r:190, g:190, b:200, a:255},
+    weight: 2,
+    kind: ItemKind::Weapon,
+    effect: None,
+    modifiers: Modifiers { attack: 2, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static SWORD: ItemType = ItemType {
+    name: "Sword",
+    glyph: ')',
+    colour: Colour{r:220, g:220, b:235, a:255},
+    weight: 6,
+    kind: ItemKind::Weapon,
+    effect: None,
+    modifiers: Modifiers { attack: 4, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static SHORT_BOW: ItemType = ItemType {
+    name: "Short bow",
+    glyph: '}',
+    colour: Colour{r:170, g:120, b:70, a:255},
+    weight: 3,
+    kind: ItemKind::Weapon,
+    effect: None,
+    modifiers: Modifiers { attack: 1, ..NO_MODIFIERS },
+    range: 8
+};
+
+pub static LEATHER_ARMOUR: ItemType = ItemType {
+    name: "Leather armour",
+    glyph: '[',
+    colour: Colour{r:150, g:100, b:60, a:255},
+    weight: 10,
+    kind: ItemKind::Armour,
+    effect: None,
+    modifiers: Modifiers { defence: 2, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static CHAIN_MAIL: ItemType = ItemType {
+    name: "Chain mail",
+    glyph: '[',
+    colour: Colour{r:170, g:170, b:180, a:255},
+    weight: 25,
+    kind: ItemKind::Armour,
+    effect: None,
+    modifiers: Modifiers { defence: 4, speed: -1, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static GEM: ItemType = ItemType {
+    name: "Gem",
+    glyph: '*',
+    colour: Colour{r:120, g:220, b:255, a:255},
+    weight: 1,
+    kind: ItemKind::Treasure,
+    effect: None,
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static RING_OF_FIRE_RESISTANCE: ItemType = ItemType {
+    name: "Ring of fire resistance",
+    glyph: '=',
+    colour: Colour{r:240, g:120, b:40, a:255},
+    weight: 1,
+    kind: ItemKind::Ring,
+    effect: None,
+    modifiers: Modifiers { fire_resistance: 75, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static RING_OF_LIGHT: ItemType = ItemType {
+    name: "Ring of light",
+    glyph: '=',
+    colour: Colour{r:250, g:240, b:150, a:255},
+    weight: 1,
+    kind: ItemKind::Ring,
+    effect: None,
+    modifiers: Modifiers { light_radius: 3, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static RING_OF_SPEED: ItemType = ItemType {
+    name: "Ring of speed",
+    glyph: '=',
+    colour: Colour{r:120, g:240, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Ring,
+    effect: None,
+    modifiers: Modifiers { speed: 2, ..NO_MODIFIERS },
+    range: 0
+};
+
+pub static POTION_OF_HEALING: ItemType = ItemType {
+    name: "Potion of healing",
+    glyph: '!',
+    colour: Colour{r:230, g:60, b:90, a:255},
+    weight: 1,
+    kind: ItemKind::Potion,
+    effect: Some(Effect::Heal(15)),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static POTION_OF_BLINKING: ItemType = ItemType {
+    name: "Potion of blinking",
+    glyph: '!',
+    colour: Colour{r:160, g:90, b:230, a:255},
+    weight: 1,
+    kind: ItemKind::Potion,
+    effect: Some(Effect::Teleport),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static POTION_OF_POISON: ItemType = ItemType {
+    name: "Potion of poison",
+    glyph: '!',
+    colour: Colour{r:110, g:200, b:60, a:255},
+    weight: 1,
+    kind: ItemKind::Potion,
+    effect: Some(Effect::Inflict(Status { kind: StatusKind::Poisoned, turns: 20 })),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SCROLL_OF_TELEPORTATION: ItemType = ItemType {
+    name: "Scroll of teleportation",
+    glyph: '?',
+    colour: Colour{r:240, g:230, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Scroll,
+    effect: Some(Effect::Teleport),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SCROLL_OF_MAGIC_MAPPING: ItemType = ItemType {
+    name: "Scroll of magic mapping",
+    glyph: '?',
+    colour: Colour{r:240, g:230, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Scroll,
+    effect: Some(Effect::MagicMapping),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SCROLL_OF_FEAR: ItemType = ItemType {
+    name: "Scroll of scare monsters",
+    glyph: '?',
+    colour: Colour{r:240, g:230, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Scroll,
+    effect: Some(Effect::Fear(10)),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SCROLL_OF_IDENTIFY: ItemType = ItemType {
+    name: "Scroll of identify",
+    glyph: '?',
+    colour: Colour{r:240, g:230, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Scroll,
+    effect: Some(Effect::Identify),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SCROLL_OF_FROST: ItemType = ItemType {
+    name: "Scroll of frost",
+    glyph: '?',
+    colour: Colour{r:240, g:230, b:200, a:255},
+    weight: 1,
+    kind: ItemKind::Scroll,
+    effect: Some(Effect::Freeze(5)),
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static COPPER_KEY: ItemType = ItemType {
+    name: "Copper key",
+    glyph: '-',
+    colour: Colour{r:200, g:120, b:60, a:255},
+    weight: 0,
+    kind: ItemKind::Key,
+    effect: None,
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static SILVER_KEY: ItemType = ItemType {
+    name: "Silver key",
+    glyph: '-',
+    colour: Colour{r:200, g:200, b:215, a:255},
+    weight: 0,
+    kind: ItemKind::Key,
+    effect: None,
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+pub static GOLD_KEY: ItemType = ItemType {
+    name: "Gold key",
+    glyph: '-',
+    colour: Colour{r:240, g:200, b:50, a:255},
+    weight: 0,
+    kind: ItemKind::Key,
+    effect: None,
+    modifiers: NO_MODIFIERS,
+    range: 0
+};
+
+// One for each locked door the generator can place, in order
+pub static KEYS: [&ItemType; 3] = [&COPPER_KEY, &SILVER_KEY, &GOLD_KEY];
+
+// Everything the generator can scatter on the floor
+pub static FLOOR_ITEMS: [&ItemType; 17] = [
+    &DAGGER, &SWORD, &SHORT_BOW, &LEATHER_ARMOUR, &CHAIN_MAIL, &GEM,
+    &RING_OF_FIRE_RESISTANCE, &RING_OF_LIGHT, &RING_OF_SPEED,
+    &POTION_OF_HEALING, &POTION_OF_BLINKING, &POTION_OF_POISON,
+    &SCROLL_OF_TELEPORTATION, &SCROLL_OF_MAGIC_MAPPING, &SCROLL_OF_FEAR, &SCROLL_OF_IDENTIFY,
+    &SCROLL_OF_FROST
+];
+
+const POTION_LOOKS: [&str; 6] = ["murky", "bubbling", "smoky", "fizzy", "glowing", "cloudy"];
+const SCROLL_SYLLABLES: [&str; 12] = ["ZEL", "GO", "MER", "FOO", "XAN", "THU", "VE", "KLA", "ORB", "NIX", "DAE", "PRA"];
+
+// Potions and scrolls go by a random description until identified, these
+// are shuffled every game
+pub fn random_appearances(rng: &mut StdRng) -> HashMap<&'static str, String> {
+    let mut looks = POTION_LOOKS.to_vec();
+    looks.shuffle(rng);
+
+    let mut appearances = HashMap::new();
+    for item_type in FLOOR_ITEMS.iter() {
+        match item_type.kind {
+            ItemKind::Potion => {
+                let look = looks.pop().unwrap_or("strange");
+                appearances.insert(item_type.name, format!("{} potion", look));
+            },
+            ItemKind::Scroll => {
+                let mut label = String::new();
+                for word in 0..2 {
+                    if word > 0 { label.push(' '); }
+                    for _ in 0..rng.gen_range(1, 3) {
+                        label.push_str(SCROLL_SYLLABLES[rng.gen_range(0, SCROLL_SYLLABLES.len())]);
+                    }
+                }
+                appearances.insert(item_type.name, format!("scroll labelled {}", label));
+            },
+            _ => {}
+        }
+    }
+    appearances
+}
+
+pub fn spawn(world: &mut World, item_type: &'static ItemType, pos: Vec2i) -> Entity {
+    let e = world.spawn();
+    world.positions.insert(e, pos);
+    world.renderables.insert(e, Renderable { glyph: item_type.glyph, colour: item_type.colour, layer: LAYER_ITEM });
+    world.names.insert(e, item_type.name.to_string());
+    world.items.insert(e, Item { item_type });
+    e
+}
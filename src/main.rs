@@ -1,33 +1,65 @@
 extern crate sdl2;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use std::path::Path;
 use std::time::Duration;
 
 mod action;
-mod actor;
-mod drawable;
+mod camera;
+mod components;
+mod ecs;
+mod effect;
+mod energy;
+mod event;
+mod font;
 mod game;
+mod headless;
 mod hero;
 mod input;
+mod item;
 mod math;
 mod maze;
+mod message;
+mod minimap;
 mod monster;
+mod occupancy;
+mod presentation;
+mod progression;
+mod renderer;
+mod sdl_renderer;
+mod spell;
+mod stats;
+mod status;
+mod systems;
+mod terminal;
+mod tileset;
+mod trap;
+mod world;
 
-use crate::action::WalkAction;
-use crate::game::Direction;
 use crate::game::Game;
 use crate::input::Input;
+use crate::sdl_renderer::SdlRenderer;
 
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--headless") {
+        headless::run(&args);
+        return;
+    }
+    if args.iter().any(|a| a == "--terminal") {
+        terminal::run();
+        return;
+    }
 
     let sdl = sdl2::init().unwrap();
     let video_subsystem = sdl.video().unwrap();
     let window = video_subsystem
-        .window("Dungeon", 784, 592)
+        .window("Dungeon", 784, 672)
         .resizable()
+        .allow_highdpi()
         .build()
         .unwrap();
 
@@ -37,32 +69,68 @@ fn main() {
         .build()
         .unwrap();
 
-    let mut game: Game = Game::new(canvas);
-
-    fn handle_input(game: &mut Game, input: Input) {
-        match input {
-            Input::North => game.hero.set_next_action(Box::new(WalkAction{direction: Direction::North})),
-            Input::South => game.hero.set_next_action(Box::new(WalkAction{direction: Direction::South})),
-            Input::East => game.hero.set_next_action(Box::new(WalkAction{direction: Direction::East})),
-            Input::West => game.hero.set_next_action(Box::new(WalkAction{direction: Direction::West})),
+    let texture_creator = canvas.texture_creator();
+    let mut renderer = SdlRenderer::new(canvas);
+    let ui_scale = args.iter().position(|a| a == "--ui-scale")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|s| s.parse::<f32>().ok());
+    if let Some(scale) = ui_scale {
+        renderer.set_ui_scale(scale);
+    }
+    // Without a tileset everything is drawn as coloured squares
+    let tileset = args.iter().position(|a| a == "--tileset").and_then(|i| args.get(i + 1));
+    if let Some(path) = tileset {
+        if let Err(e) = renderer.load_tileset(Path::new(path), &texture_creator) {
+            eprintln!("{}", e);
         }
     }
+    let mut game: Game = Game::new();
 
     let mut event_pump = sdl.event_pump().unwrap();
     'main: loop {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit {..} => break 'main,
-                Event::KeyDown { keycode: Some(Keycode::Up), ..} => handle_input(&mut game, Input::North),
-                Event::KeyDown { keycode: Some(Keycode::Down), ..} => handle_input(&mut game, Input::South),
-                Event::KeyDown { keycode: Some(Keycode::Left), ..} => handle_input(&mut game, Input::West),
-                Event::KeyDown { keycode: Some(Keycode::Right), ..} => handle_input(&mut game, Input::East),
+                Event::Window { win_event: WindowEvent::SizeChanged(..), ..} => renderer.resize(),
+                Event::KeyDown { keycode: Some(Keycode::Up), ..} => game.handle_input(Input::North),
+                Event::KeyDown { keycode: Some(Keycode::Down), ..} => game.handle_input(Input::South),
+                Event::KeyDown { keycode: Some(Keycode::Left), ..} => game.handle_input(Input::West),
+                Event::KeyDown { keycode: Some(Keycode::Right), ..} => game.handle_input(Input::East),
+                Event::KeyDown { keycode: Some(Keycode::Space), ..} |
+                Event::KeyDown { keycode: Some(Keycode::Period), ..} |
+                Event::KeyDown { keycode: Some(Keycode::Kp5), ..} => game.handle_input(Input::Wait),
+                Event::KeyDown { keycode: Some(Keycode::Escape), ..} => game.handle_input(Input::Cancel),
+                Event::KeyDown { keycode: Some(Keycode::Return), ..} |
+                Event::KeyDown { keycode: Some(Keycode::KpEnter), ..} => game.handle_input(Input::Confirm),
+                Event::KeyDown { keycode: Some(Keycode::Tab), ..} => game.handle_input(Input::Next),
+                Event::KeyDown { keycode: Some(Keycode::Comma), ..} => game.handle_input(Input::Key(',')),
+                Event::KeyDown { keycode: Some(Keycode::Equals), ..} |
+                Event::KeyDown { keycode: Some(Keycode::Plus), ..} |
+                Event::KeyDown { keycode: Some(Keycode::KpPlus), ..} => game.handle_input(Input::Key('+')),
+                Event::KeyDown { keycode: Some(Keycode::Minus), ..} |
+                Event::KeyDown { keycode: Some(Keycode::KpMinus), ..} => game.handle_input(Input::Key('-')),
+                Event::MouseMotion { x, y, ..} => game.handle_input(Input::Hover(renderer.cell_at(x, y))),
+                Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, ..} =>
+                    game.handle_input(Input::Click(renderer.cell_at(x, y))),
+                Event::KeyDown { keycode: Some(k), ..} => {
+                    // Letter keycodes match their lowercase ASCII codes
+                    let code = k as i32;
+                    if code >= 'a' as i32 && code <= 'z' as i32 {
+                        game.handle_input(Input::Key(code as u8 as char));
+                    }
+                },
                 _ => {}
             }
         }
 
-        game.update();
-        game.draw();
+        if game.has_quit() {
+            break 'main;
+        }
+
+        // The world only advances on input, animation and drawing carry on
+        // every frame
+        game.tick();
+        game.draw(&mut renderer);
 
         std::thread::sleep(Duration::new(0, 1000000000u32 / 60));
     }
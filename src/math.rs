@@ -2,9 +2,9 @@
 use std::ops::{Add, Sub, Mul, Div};
 
 pub type Vec2i = Vec2<i32>;
-pub type _Vec2f = Vec2<f32>;
+pub type Vec2f = Vec2<f32>;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vec2<T> {
     pub x: T,
     pub y: T
@@ -16,6 +16,12 @@ impl <T> Vec2<T> {
     }
 }
 
+impl Vec2i {
+    pub fn to_f32(self) -> Vec2f {
+        Vec2f::new(self.x as f32, self.y as f32)
+    }
+}
+
 impl<T: Add<Output = T>> Add for Vec2<T> {
     type Output = Vec2<T>;
 
@@ -47,3 +53,71 @@ impl<T: Div<Output = T>> Div for Vec2<T> {
         Vec2::<T> { x: self.x / rhs.x, y: self.y / rhs.y }
     }
 }
+
+// Bresenham's line between two points, both ends included
+pub fn line(start: Vec2i, end: Vec2i) -> Vec<Vec2i> {
+    let dx = (end.x - start.x).abs();
+    let dy = -(end.y - start.y).abs();
+    let step_x = if start.x < end.x { 1 } else { -1 };
+    let step_y = if start.y < end.y { 1 } else { -1 };
+
+    let mut error = dx + dy;
+    let mut p = start;
+    let mut points = vec![p];
+    while p != end {
+        let e2 = 2*error;
+        if e2 >= dy {
+            error += dy;
+            p.x += step_x;
+        }
+        if e2 <= dx {
+            error += dx;
+            p.y += step_y;
+        }
+        points.push(p);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(p: &[(i32, i32)]) -> Vec<Vec2i> {
+        p.iter().map(|&(x, y)| Vec2i::new(x, y)).collect()
+    }
+
+    #[test]
+    fn line_to_itself_is_one_point() {
+        assert_eq!(line(Vec2i::new(3, 4), Vec2i::new(3, 4)), points(&[(3, 4)]));
+    }
+
+    #[test]
+    fn straight_and_diagonal_lines() {
+        assert_eq!(line(Vec2i::new(0, 0), Vec2i::new(3, 0)), points(&[(0, 0), (1, 0), (2, 0), (3, 0)]));
+        assert_eq!(line(Vec2i::new(0, 0), Vec2i::new(0, -2)), points(&[(0, 0), (0, -1), (0, -2)]));
+        assert_eq!(line(Vec2i::new(2, 2), Vec2i::new(-1, -1)), points(&[(2, 2), (1, 1), (0, 0), (-1, -1)]));
+    }
+
+    #[test]
+    fn shallow_line() {
+        assert_eq!(line(Vec2i::new(0, 0), Vec2i::new(4, 2)), points(&[(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]));
+    }
+
+    // Every step moves to a neighbouring cell and the line ends where asked
+    #[test]
+    fn lines_are_connected() {
+        for x in -5..=5 {
+            for y in -5..=5 {
+                let end = Vec2i::new(x, y);
+                let path = line(Vec2i::new(0, 0), end);
+                assert_eq!(*path.last().unwrap(), end);
+                assert_eq!(path.len() as i32, x.abs().max(y.abs()) + 1);
+                for pair in path.windows(2) {
+                    let d = pair[1] - pair[0];
+                    assert!(d.x.abs() <= 1 && d.y.abs() <= 1);
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,10 @@
 extern crate rand;
 use rand::Rng;
+use rand::seq::SliceRandom;
 use std::collections::HashSet;
 use std::collections::HashMap;
 
 
-use crate::game::Colour;
 use crate::game::Level;
 use crate::game::Tile;
 use crate::game::TileType;
@@ -12,6 +12,33 @@ use crate::game::TileType;
 const EMPTY: i32 = -2;
 const WALL: i32 = -1;
 
+// Rooms take the ids below this, maze corridors the ones above
+const FIRST_MAZE_REGION: i32 = 100;
+
+// The most locked doors a level gets
+const MAX_LOCKS: usize = 3;
+
+// A connector that was opened up between two regions
+#[derive(Copy, Clone)]
+struct Connection {
+    pos: (usize, usize),
+    regions: (i32, i32)
+}
+
+// A locked door and where its key was left
+pub struct Lock {
+    pub door: (usize, usize),
+    pub key: (usize, usize)
+}
+
+// Everything the generator decides: the tiles, where the hero starts and
+// which doors are locked
+pub struct Layout {
+    pub level: Level,
+    pub start: (i32, i32),
+    pub locks: Vec<Lock>
+}
+
 struct Regions {
     width: usize,
     height: usize,
@@ -44,10 +71,10 @@ impl std::ops::IndexMut<usize> for Regions {
 pub struct Maze {}
 
 impl Maze {
-    pub fn new(width: usize, height: usize) -> Level {
+    pub fn new(width: usize, height: usize) -> Layout {
         let mut level = Level::new(width, height);
         let mut regions = Regions::new(width, height);
-        let mut num_regions: i32 = 100;
+        let mut num_regions: i32 = FIRST_MAZE_REGION;
 
         Maze::fill_perimeter(&mut regions);
         Maze::gen_random_rooms(&mut regions, num_regions);
@@ -57,10 +84,19 @@ impl Maze {
                 num_regions += 1;
             }
         }
-        Maze::connect_regions(&mut regions);
+        let connections = Maze::connect_regions(&mut regions);
         Maze::remove_deadends(&mut regions);
         Maze::regions_to_tiles(&regions, &mut level);
-        return level;
+        Maze::place_doors(&connections, &mut level);
+        Maze::place_hazards(&mut level);
+
+        let start_region = Maze::choose_start_region(&regions, &level);
+        let start = Maze::find_cell_in_region(&regions, &level, start_region).unwrap_or_else(|| {
+            let (x, y) = Maze::find_spawn(&level);
+            (x as usize, y as usize)
+        });
+        let locks = Maze::place_locks(&connections, &regions, &mut level, start_region, start);
+        return Layout { level, start: (start.0 as i32, start.1 as i32), locks };
     }
 
     // Mark all the edges as walls
@@ -169,7 +205,8 @@ impl Maze {
         }
     }
 
-    fn connect_regions(regions: &mut Regions) {
+    // Returns the cells that were opened up to join regions together
+    fn connect_regions(regions: &mut Regions) -> Vec<Connection> {
 
         fn get_neighbouring_regions(regions: &mut Regions, (x,y):(usize, usize)) -> HashSet<i32> {
             let mut neighbours = HashSet::new();
@@ -196,6 +233,7 @@ impl Maze {
             }
         }
 
+        let mut opened: Vec<Connection> = Vec::new();
         let mut mappings: HashMap<i32, Vec<i32>> = HashMap::new();
         for room in connectors {
             // Build a list of wall cells that touch a region not yet merged
@@ -213,6 +251,10 @@ impl Maze {
                 let (x,y) = (candidates[idx].0, candidates[idx].1);
                 regions[y][x] = 0;
                 let mergees = get_neighbouring_regions(regions, (x, y));
+                let joined: Vec<i32> = mergees.iter().cloned().collect();
+                if joined.len() == 2 {
+                    opened.push(Connection { pos: (x, y), regions: (joined[0], joined[1]) });
+                }
                 for m in &mergees {
                     for n in &mergees {
                         mappings.entry(*m).or_insert_with(Vec::new).push(*n);
@@ -220,6 +262,7 @@ impl Maze {
                 }
             }
         }
+        opened
     }
 
     fn remove_deadends(regions: &mut Regions) {
@@ -250,14 +293,184 @@ impl Maze {
         for j in 0..regions.height {
             for i in 0..regions.width {
                 match regions[j][i] {
-                    EMPTY => level[j][i] = Tile{tile_type: TileType::_Empty, colour: Colour{r:64,g:64,b:64,a:255}},
-                    WALL => level[j][i] = Tile{tile_type: TileType::_Wall, colour: Colour{r:0,g:0,b:0,a:255}},
-                    _ => level[j][i] = Tile{tile_type: TileType::_Floor, colour: Colour{r:128,g:128,b:128,a:255}},
+                    EMPTY => level[j][i] = Tile::new(TileType::_Empty),
+                    WALL => level[j][i] = Tile::new(TileType::_Wall),
+                    _ => level[j][i] = Tile::new(TileType::_Floor),
                 }
             }
         }
     }
 
+    // Turn some of the connectors into doors where they sit in a doorway,
+    // a few of them hidden
+    fn place_doors(connections: &[Connection], level: &mut Level) {
+        for &Connection { pos: (x, y), .. } in connections {
+            if level[y][x].tile_type != TileType::_Floor { continue; }
+
+            let floor = |i: usize, j: usize| level[j][i].tile_type == TileType::_Floor;
+            let horizontal = floor(x-1, y) && floor(x+1, y) && !floor(x, y-1) && !floor(x, y+1);
+            let vertical = floor(x, y-1) && floor(x, y+1) && !floor(x-1, y) && !floor(x+1, y);
+
+            if horizontal || vertical {
+                match rand::thread_rng().gen_range(0, 8) {
+                    0..=3 => level[y][x] = Tile::new(TileType::_Door),
+                    4 => level[y][x] = Tile::new(TileType::_SecretDoor),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Scatter a few small patches of fire, ice and water across open floor
+    fn place_hazards(level: &mut Level) {
+        for n in 0..9 {
+            let tile_type = match n % 3 {
+                0 => TileType::_Fire,
+                1 => TileType::_Ice,
+                _ => TileType::_Water
+            };
+            let (x, y) = Maze::find_spawn(level);
+            let (x, y) = (x as usize, y as usize);
+            for &(i, j) in [(x, y), (x-1, y), (x+1, y), (x, y-1), (x, y+1)].iter() {
+                if rand::thread_rng().gen_range(0, 3) > 0 || (i, j) == (x, y) {
+                    level[j][i] = Tile::new(tile_type);
+                }
+            }
+        }
+    }
+
+    // Start in a random room, falling back on any region with room to stand
+    fn choose_start_region(regions: &Regions, level: &Level) -> i32 {
+        let mut rooms: Vec<i32> = regions.grid.iter().cloned()
+            .filter(|&r| (0..FIRST_MAZE_REGION).contains(&r))
+            .collect::<HashSet<i32>>().into_iter()
+            .filter(|&r| Maze::find_cell_in_region(regions, level, r).is_some())
+            .collect();
+        rooms.sort();
+        if rooms.is_empty() {
+            return -1;
+        }
+        rooms[rand::thread_rng().gen_range(0, rooms.len())]
+    }
+
+    // A random open floor cell well inside the region
+    fn find_cell_in_region(regions: &Regions, level: &Level, region: i32) -> Option<(usize, usize)> {
+        let open = |x: usize, y: usize| level[y][x].tile_type == TileType::_Floor;
+        let mut cells = Vec::new();
+        for j in 1..regions.height-1 {
+            for i in 1..regions.width-1 {
+                if regions[j][i] == region && open(i, j) && open(i-1, j) && open(i+1, j) && open(i, j-1) && open(i, j+1) {
+                    cells.push((i, j));
+                }
+            }
+        }
+        cells.choose(&mut rand::thread_rng()).cloned()
+    }
+
+    // Regions that can be reached from the start. Locked connections only
+    // open once the region holding their key has been reached
+    fn reachable(connections: &[Connection], start: i32, locks: &[(usize, i32)]) -> HashSet<i32> {
+        let mut reached = HashSet::new();
+        reached.insert(start);
+        loop {
+            let mut changed = false;
+            for (i, c) in connections.iter().enumerate() {
+                let open = match locks.iter().find(|(door, _)| *door == i) {
+                    Some((_, key_region)) => reached.contains(key_region),
+                    None => true
+                };
+                if !open { continue; }
+                let (a, b) = c.regions;
+                if reached.contains(&a) != reached.contains(&b) {
+                    reached.insert(a);
+                    reached.insert(b);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return reached;
+            }
+        }
+    }
+
+    // Lock a few doors, leaving each key somewhere it can be fetched from
+    // without going through that door. A lock is only kept if the whole
+    // level can still be reached by picking up keys in some order
+    fn place_locks(connections: &[Connection], regions: &Regions, level: &mut Level,
+                   start_region: i32, start: (usize, usize)) -> Vec<Lock> {
+        // Connectors lost to dead end removal don't join anything any more
+        let graph: Vec<Connection> = connections.iter()
+            .filter(|c| level[c.pos.1][c.pos.0].can_walk() || level[c.pos.1][c.pos.0].tile_type == TileType::_Door)
+            .cloned()
+            .collect();
+        let everything = Maze::reachable(&graph, start_region, &[]);
+
+        let mut candidates: Vec<usize> = (0..graph.len())
+            .filter(|&i| level[graph[i].pos.1][graph[i].pos.0].tile_type == TileType::_Door)
+            .collect();
+        candidates.shuffle(&mut rand::thread_rng());
+
+        // Each lock is the connection index and the region its key is in
+        let mut locks: Vec<(usize, i32)> = Vec::new();
+        let mut keys: Vec<(usize, usize)> = Vec::new();
+        for door in candidates {
+            if locks.len() >= MAX_LOCKS { break; }
+
+            // Pretend the key is lost to see what the door keeps out
+            let mut trial = locks.clone();
+            trial.push((door, EMPTY));
+            let before = Maze::reachable(&graph, start_region, &trial);
+            if before.len() == everything.len() { continue; }
+
+            let mut key_regions: Vec<i32> = before.iter().cloned()
+                .filter(|&r| r < FIRST_MAZE_REGION)
+                .collect();
+            key_regions.sort();
+            key_regions.shuffle(&mut rand::thread_rng());
+            let key = key_regions.iter().find_map(|&r| {
+                Maze::find_cell_in_region(regions, level, r)
+                    .filter(|&cell| cell != start && !keys.contains(&cell))
+                    .map(|cell| (r, cell))
+            });
+            let (key_region, key_cell) = match key {
+                Some(key) => key,
+                None => continue
+            };
+
+            trial.pop();
+            trial.push((door, key_region));
+            if Maze::reachable(&graph, start_region, &trial).len() == everything.len() {
+                locks = trial;
+                keys.push(key_cell);
+            }
+        }
+
+        locks.iter().zip(keys).map(|(&(door, _), key)| {
+            let (x, y) = graph[door].pos;
+            level[y][x] = Tile::new(TileType::_LockedDoor);
+            Lock { door: (x, y), key }
+        }).collect()
+    }
+
+    // Cells in corridors and doorways, where only two opposite sides are open
+    pub fn find_trap_sites(level: &Level, count: usize) -> Vec<(i32, i32)> {
+        let open = |i: usize, j: usize| level[j][i].can_walk() || level[j][i].tile_type == TileType::_Door;
+        let mut sites = Vec::new();
+        for j in 1..level.height-1 {
+            for i in 1..level.width-1 {
+                if level[j][i].tile_type != TileType::_Floor { continue; }
+                let horizontal = open(i-1, j) && open(i+1, j) && !open(i, j-1) && !open(i, j+1);
+                let vertical = open(i, j-1) && open(i, j+1) && !open(i-1, j) && !open(i+1, j);
+                if horizontal || vertical {
+                    sites.push((i as i32, j as i32));
+                }
+            }
+        }
+        sites.shuffle(&mut rand::thread_rng());
+        sites.truncate(count);
+        sites
+    }
+
     pub fn find_spawn(level: &Level) -> (i32, i32) {
 
         fn valid_spawn(level: &Level, x: usize, y: usize) -> bool {
@@ -276,4 +489,65 @@ impl Maze {
         }
         (x as i32,y as i32)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    // Cells the hero can walk to from the start with the given doors still
+    // locked. Hidden doors count as open since searching finds them
+    fn walkable_from(layout: &Layout, locked: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+        let level = &layout.level;
+        let start = (layout.start.0 as usize, layout.start.1 as usize);
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(start);
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            for &(nx, ny) in &[(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                let tile = level[ny][nx];
+                let passable = tile.can_walk() || match tile.tile_type {
+                    TileType::_Door | TileType::_SecretDoor => true,
+                    TileType::_LockedDoor => !locked.contains(&(nx, ny)),
+                    _ => false
+                };
+                if passable && seen.insert((nx, ny)) {
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn every_key_can_be_fetched() {
+        let mut placed = 0;
+        for _ in 0..100 {
+            let layout = Maze::new(49, 37);
+            placed += layout.locks.len();
+
+            // No key is behind its own door
+            for lock in layout.locks.iter() {
+                assert!(walkable_from(&layout, &[lock.door]).contains(&lock.key));
+            }
+
+            // And picking up whatever keys are in reach eventually opens
+            // every door
+            let mut locked: Vec<(usize, usize)> = layout.locks.iter().map(|l| l.door).collect();
+            loop {
+                let reached = walkable_from(&layout, &locked);
+                let before = locked.len();
+                locked.retain(|door| {
+                    let lock = layout.locks.iter().find(|l| l.door == *door).unwrap();
+                    !reached.contains(&lock.key)
+                });
+                if locked.len() == before {
+                    break;
+                }
+            }
+            assert!(locked.is_empty());
+        }
+        assert!(placed > 0);
+    }
+}
@@ -0,0 +1,30 @@
+pub struct MessageLog {
+    messages: Vec<String>,
+    read: usize
+}
+
+impl MessageLog {
+    pub fn new() -> MessageLog {
+        MessageLog { messages: Vec::new(), read: 0 }
+    }
+
+    pub fn add(&mut self, message: String) {
+        self.messages.push(message);
+    }
+
+    pub fn all(&self) -> &[String] {
+        &self.messages
+    }
+
+    // The last few messages, oldest first
+    pub fn recent(&self, count: usize) -> &[String] {
+        &self.messages[self.messages.len().saturating_sub(count)..]
+    }
+
+    // Returns the messages added since the last call
+    pub fn take_new(&mut self) -> &[String] {
+        let start = self.read;
+        self.read = self.messages.len();
+        &self.messages[start..]
+    }
+}
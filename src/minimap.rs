@@ -0,0 +1,55 @@
+use crate::game::{Colour, TileType};
+use crate::math::Vec2i;
+use crate::world::World;
+
+// The level seen from above, one colour per map cell, with what matters most
+// picked out on top. Backends scale it to whatever room they are given and
+// keep the marks visible when they have to shrink it
+pub struct MapImage {
+    pub width: usize,
+    pub height: usize,
+    // None where the hero hasn't been
+    pub tiles: Vec<Option<Colour>>,
+    pub marks: Vec<Mark>
+}
+
+pub struct Mark {
+    pub pos: Vec2i,
+    pub glyph: char,
+    pub colour: Colour
+}
+
+// Everything the hero knows about: explored tiles, monsters in sight and the
+// hero last so it is never covered
+pub fn build(world: &World) -> MapImage {
+    let level = &world.level;
+    let mut tiles = Vec::with_capacity(level.width*level.height);
+    for j in 0..level.height {
+        for i in 0..level.width {
+            let pos = Vec2i::new(i as i32, j as i32);
+            if !level.is_explored(pos) {
+                tiles.push(None);
+                continue;
+            }
+            let tile = level[j][i];
+            let colour = match tile.tile_type {
+                TileType::_Floor => Colour::new(50, 50, 60, 255),
+                TileType::_Empty | TileType::_Wall | TileType::_SecretDoor => Colour::new(150, 150, 150, 255),
+                _ => tile.colour
+            };
+            tiles.push(Some(colour));
+        }
+    }
+
+    let mut marks = Vec::new();
+    for e in world.ais.entities() {
+        let pos = world.position(e);
+        if level.is_visible(pos) {
+            let glyph = world.renderables.get(e).map_or('m', |r| r.glyph);
+            marks.push(Mark { pos, glyph, colour: Colour::new(230, 60, 60, 255) });
+        }
+    }
+    marks.push(Mark { pos: world.position(world.hero), glyph: '@', colour: Colour::new(255, 255, 255, 255) });
+
+    MapImage { width: level.width, height: level.height, tiles, marks }
+}
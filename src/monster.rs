@@ -1,55 +1,210 @@
-use crate::actor::Actor;
+extern crate rand;
+use rand::Rng;
+
 use crate::action::Action;
-use crate::drawable::Drawable;
+use crate::action::AttackAction;
+use crate::action::CastAction;
+use crate::action::WaitAction;
+use crate::action::WalkAction;
+use crate::components::*;
+use crate::ecs::Entity;
+use crate::energy::{FAST_SPEED, NORMAL_SPEED, SLOW_SPEED};
 use crate::game::Colour;
+use crate::game::Direction;
 use crate::math::Vec2i;
-use crate::action::NullAction;
-
-#[derive(Copy, Clone)]
-pub struct Breed <'a> {
-    name: &'a str,
-    health: u32,
-    speed: u32,
-    clustered: bool
-}
+use crate::spell;
+use crate::stats::Stats;
+use crate::status::{Status, StatusKind};
+use crate::systems;
+use crate::world::World;
+
+const SIGHT_RANGE: i32 = 8;
 
-#[derive(Copy, Clone)]
-pub struct Monster<'a> {
-    drawable: Drawable,
-    breed: Breed<'a>
+// One in this many turns a caster with the hero in sight casts something
+const CAST_CHANCE: u32 = 2;
+
+// Sleeping monsters wake when the hero comes this close, or now and then anyway
+const WAKE_RANGE: i32 = 4;
+const WAKE_CHANCE: u32 = 40;
+
+pub struct Breed {
+    pub name: &'static str,
+    pub glyph: char,
+    pub colour: Colour,
+    pub health: u32,
+    pub attack: u32,
+    pub defence: u32,
+    pub speed: u32,
+    pub clustered: bool,
+    // Experience the hero gets for killing one
+    pub experience: u32,
+    // Inflicted on whatever it hits
+    pub hit_status: Option<Status>,
+    // Casters need mana as well as spells to cast
+    pub mana: u32,
+    pub spells: &'static [&'static str]
 }
 
-impl <'a> Actor for Monster<'a> {
-    fn get_drawable(&self) -> Drawable {
-        self.drawable
+pub static GOBLIN: Breed = Breed {
+    name: "Goblin",
+    glyph: 'g',
+    colour: Colour{r:65, g:146, b:75, a:255},
+    health: 10,
+    attack: 4,
+    defence: 1,
+    speed: NORMAL_SPEED,
+    clustered: false,
+    experience: 6,
+    hit_status: None,
+    mana: 0,
+    spells: &[]
+};
+
+pub static RAT: Breed = Breed {
+    name: "Rat",
+    glyph: 'r',
+    colour: Colour{r:139, g:105, b:80, a:255},
+    health: 4,
+    attack: 2,
+    defence: 0,
+    speed: FAST_SPEED,
+    clustered: true,
+    experience: 2,
+    hit_status: Some(Status { kind: StatusKind::Poisoned, turns: 6 }),
+    mana: 0,
+    spells: &[]
+};
+
+pub static SLIME: Breed = Breed {
+    name: "Slime",
+    glyph: 's',
+    colour: Colour{r:64, g:160, b:200, a:255},
+    health: 16,
+    attack: 3,
+    defence: 3,
+    speed: SLOW_SPEED,
+    clustered: false,
+    experience: 8,
+    hit_status: Some(Status { kind: StatusKind::Slowed, turns: 3 }),
+    mana: 0,
+    spells: &[]
+};
+
+pub static GOBLIN_SHAMAN: Breed = Breed {
+    name: "Goblin shaman",
+    glyph: 'g',
+    colour: Colour{r:150, g:70, b:170, a:255},
+    health: 8,
+    attack: 2,
+    defence: 1,
+    speed: NORMAL_SPEED,
+    clustered: false,
+    experience: 10,
+    hit_status: None,
+    mana: 12,
+    spells: &["Fire bolt", "Slow"]
+};
+
+pub fn spawn(world: &mut World, breed: &'static Breed, pos: Vec2i) -> Entity {
+    let e = world.spawn();
+    world.positions.insert(e, pos);
+    world.occupancy.add(pos, e);
+    world.renderables.insert(e, Renderable { glyph: breed.glyph, colour: breed.colour, layer: LAYER_ACTOR });
+    world.names.insert(e, breed.name.to_string());
+    world.stats.insert(e, Stats::new(breed.health, breed.attack, breed.defence));
+    world.speeds.insert(e, Speed::new(breed.speed));
+    world.factions.insert(e, Faction::Monsters);
+    let asleep = world.rng.gen_range(0, 2) == 0;
+    world.ais.insert(e, Ai { sight_range: SIGHT_RANGE, fear: 0, asleep });
+    world.breeds.insert(e, breed);
+    if !breed.spells.is_empty() {
+        let spells = breed.spells.iter().filter_map(|name| spell::find(world, name)).collect();
+        world.mana.insert(e, Mana::new(breed.mana));
+        world.spellbooks.insert(e, Spellbook { spells });
     }
+    e
+}
+
+// Attack the hero if adjacent, chase them if close, otherwise wander.
+// Frightened monsters run away instead, sleeping ones wait to be woken
+pub fn choose_action(world: &mut World, e: Entity) -> Box<dyn Action> {
+    let pos = world.position(e);
+    let distance = |p: Vec2i| (p.x - pos.x).abs() + (p.y - pos.y).abs();
+    let hero = if world.is_hero_alive() { Some(world.position(world.hero)) } else { None };
 
-    fn get_position(&self) -> Vec2i {
-        self.drawable.pos
+    if world.ais.get(e).is_some_and(|ai| ai.asleep) {
+        let disturbed = hero.is_some_and(|h| distance(h) <= WAKE_RANGE && systems::line_of_sight(world, pos, h));
+        if !disturbed && world.rng.gen_range(0, WAKE_CHANCE) != 0 {
+            return Box::new(WaitAction{});
+        }
+        if let Some(ai) = world.ais.get_mut(e) {
+            ai.asleep = false;
+        }
     }
 
-    fn set_position(&mut self, pos: Vec2i) {
-        self.drawable.pos = pos;
+    let (sight_range, afraid) = match world.ais.get_mut(e) {
+        Some(ai) => {
+            let afraid = ai.fear > 0;
+            ai.fear = ai.fear.saturating_sub(1);
+            (ai.sight_range, afraid)
+        },
+        None => (0, false)
+    };
+
+    if let Some(target) = hero {
+        if distance(target) == 1 && !afraid {
+            return Box::new(AttackAction{target});
+        }
+        if !afraid && distance(target) <= sight_range && systems::line_of_sight(world, pos, target) {
+            if let Some(cast) = choose_spell(world, e, target) {
+                return cast;
+            }
+        }
     }
 
-    fn get_action(&self) -> Box<dyn Action> {
-        Box::new(NullAction{})
+    let options: Vec<Direction> =
+        [Direction::North, Direction::South, Direction::East, Direction::West].iter()
+        .filter(|d| world.level.can_walk(pos + d.offset()))
+        .cloned()
+        .collect();
+
+    if options.is_empty() {
+        return Box::new(WaitAction{});
     }
-}
 
-impl <'a> Monster<'a> {
-    pub fn goblin((x,y) : (i32, i32)) -> Monster<'a> {
-        Monster {
-            drawable: Drawable {
-                pos: Vec2i::new(x,y),
-                colour: Colour::new(65, 146, 75, 255)
-            },
-            breed: Breed {
-                name: "Goblin",
-                health: 10,
-                speed: 1,
-                clustered: false
+    if let Some(target) = hero {
+        if distance(target) <= sight_range {
+            let towards = |d: &Direction| {
+                let p = pos + d.offset();
+                (p.x - target.x).abs() + (p.y - target.y).abs()
+            };
+            let best = if afraid {
+                options.iter().max_by_key(|d| towards(d))
+            } else {
+                options.iter().min_by_key(|d| towards(d))
+            };
+            if let Some(&direction) = best {
+                return Box::new(WalkAction{direction});
             }
         }
     }
-}
\ No newline at end of file
+
+    let direction = options[world.rng.gen_range(0, options.len())];
+    Box::new(WalkAction{direction})
+}
+
+// Casters throw a random affordable spell at the hero every so often
+fn choose_spell(world: &mut World, e: Entity, target: Vec2i) -> Option<Box<dyn Action>> {
+    let mana = world.mana.get(e)?.current;
+    let pos = world.position(e);
+    let options: Vec<usize> = world.spellbooks.get(e)?.spells.iter()
+        .filter(|&&s| world.spells[s].mana <= mana)
+        .filter(|&&s| (target.x - pos.x).pow(2) + (target.y - pos.y).pow(2) <= world.spells[s].range.pow(2))
+        .cloned()
+        .collect();
+    if options.is_empty() || world.rng.gen_range(0, CAST_CHANCE) != 0 {
+        return None;
+    }
+    let spell = options[world.rng.gen_range(0, options.len())];
+    Some(Box::new(CastAction{spell, target}))
+}
@@ -0,0 +1,85 @@
+use crate::ecs::Entity;
+use crate::math::Vec2i;
+
+// Spatial index of which entity is standing in each cell so movement can
+// be checked without searching through every actor
+pub struct Occupancy {
+    width: usize,
+    height: usize,
+    grid: Vec<Option<Entity>>
+}
+
+impl Occupancy {
+    pub fn new(width: usize, height: usize) -> Occupancy {
+        Occupancy { width, height, grid: vec!(None; width*height) }
+    }
+
+    pub fn get(&self, pos: Vec2i) -> Option<Entity> {
+        match self.index(pos) {
+            Some(i) => self.grid[i],
+            None => None
+        }
+    }
+
+    pub fn is_occupied(&self, pos: Vec2i) -> bool {
+        self.get(pos).is_some()
+    }
+
+    pub fn add(&mut self, pos: Vec2i, e: Entity) {
+        if let Some(i) = self.index(pos) {
+            self.grid[i] = Some(e);
+        }
+    }
+
+    pub fn remove(&mut self, pos: Vec2i) {
+        if let Some(i) = self.index(pos) {
+            self.grid[i] = None;
+        }
+    }
+
+    pub fn move_occupant(&mut self, from: Vec2i, to: Vec2i) {
+        let e = self.get(from);
+        self.remove(from);
+        if let Some(e) = e {
+            self.add(to, e);
+        }
+    }
+
+    fn index(&self, pos: Vec2i) -> Option<usize> {
+        if pos.x < 0 || pos.x >= self.width as i32 { return None };
+        if pos.y < 0 || pos.y >= self.height as i32 { return None };
+        Some(pos.y as usize * self.width + pos.x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_occupant_leaves_old_cell_empty() {
+        let mut occupancy = Occupancy::new(5, 5);
+        let (from, to) = (Vec2i::new(1, 1), Vec2i::new(2, 1));
+        occupancy.add(from, Entity(7));
+        occupancy.move_occupant(from, to);
+        assert_eq!(occupancy.get(from), None);
+        assert_eq!(occupancy.get(to), Some(Entity(7)));
+    }
+
+    #[test]
+    fn moving_an_empty_cell_does_nothing() {
+        let mut occupancy = Occupancy::new(5, 5);
+        occupancy.add(Vec2i::new(2, 2), Entity(1));
+        occupancy.move_occupant(Vec2i::new(0, 0), Vec2i::new(2, 2));
+        assert_eq!(occupancy.get(Vec2i::new(2, 2)), Some(Entity(1)));
+    }
+
+    #[test]
+    fn outside_the_grid_is_never_occupied() {
+        let mut occupancy = Occupancy::new(5, 5);
+        occupancy.add(Vec2i::new(-1, 2), Entity(1));
+        occupancy.add(Vec2i::new(5, 0), Entity(2));
+        assert!(!occupancy.is_occupied(Vec2i::new(-1, 2)));
+        assert!(!occupancy.is_occupied(Vec2i::new(5, 0)));
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use crate::camera::Camera;
+use crate::ecs::Entity;
+use crate::event::Event;
+use crate::game::{Colour, Level};
+use crate::math::{Vec2f, Vec2i};
+use crate::renderer::Renderer;
+use crate::systems::Projectile;
+
+// Frame counts for each kind of animation
+const FRAMES_PER_TILE: usize = 2;
+const MOVE_FRAMES: usize = 6;
+const FLASH_FRAMES: usize = 8;
+const NUMBER_FRAMES: usize = 40;
+const TRAIL_FRAMES: usize = 12;
+
+// How far a damage number drifts up over its life, in map cells
+const NUMBER_RISE: f32 = 0.8;
+
+struct Motion {
+    entity: Entity,
+    from: Vec2i,
+    to: Vec2i,
+    frame: usize
+}
+
+struct Number {
+    pos: Vec2i,
+    text: String,
+    colour: Colour,
+    frame: usize
+}
+
+// Plays back what the world records as it happens, a frame at a time. The
+// world has already moved on by the time anything here is shown, so it only
+// changes where and how things are drawn. Events wait their turn behind a shot
+// in flight so that damage shows when the shot lands
+pub struct Presentation {
+    hero: Entity,
+    pending: VecDeque<Event>,
+    shot: Option<(Projectile, usize)>,
+    motions: Vec<Motion>,
+    flashes: Vec<(Entity, usize)>,
+    numbers: Vec<Number>,
+    trails: Vec<(Vec2i, Colour, usize)>
+}
+
+impl Presentation {
+    pub fn new(hero: Entity) -> Presentation {
+        Presentation {
+            hero,
+            pending: VecDeque::new(),
+            shot: None,
+            motions: Vec::new(),
+            flashes: Vec::new(),
+            numbers: Vec::new(),
+            trails: Vec::new()
+        }
+    }
+
+    pub fn absorb(&mut self, events: Vec<Event>) {
+        self.pending.extend(events);
+    }
+
+    // Only shots in flight are worth waiting for, the rest plays out behind
+    // whatever happens next
+    pub fn is_busy(&self) -> bool {
+        self.shot.is_some() || self.pending.iter().any(|e| matches!(e, Event::Shot(_)))
+    }
+
+    // Plays on until nothing is in flight, for when the game can't wait
+    pub fn finish_shots(&mut self) {
+        while self.is_busy() {
+            self.tick();
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for m in self.motions.iter_mut() {
+            m.frame += 1;
+        }
+        self.motions.retain(|m| m.frame < MOVE_FRAMES);
+        for f in self.flashes.iter_mut() {
+            f.1 -= 1;
+        }
+        self.flashes.retain(|f| f.1 > 0);
+        for n in self.numbers.iter_mut() {
+            n.frame += 1;
+        }
+        self.numbers.retain(|n| n.frame < NUMBER_FRAMES);
+        for t in self.trails.iter_mut() {
+            t.2 -= 1;
+        }
+        self.trails.retain(|t| t.2 > 0);
+
+        // Every cell the shot leaves behind glows for a little while
+        if let Some((projectile, frame)) = self.shot.as_mut() {
+            *frame += 1;
+            let cell = *frame / FRAMES_PER_TILE;
+            if *frame % FRAMES_PER_TILE == 0 {
+                if let Some(&pos) = projectile.path.get(cell - 1) {
+                    self.trails.push((pos, projectile.colour, TRAIL_FRAMES));
+                }
+            }
+            if cell >= projectile.path.len() {
+                self.shot = None;
+            }
+        }
+
+        while self.shot.is_none() {
+            match self.pending.pop_front() {
+                Some(Event::Shot(projectile)) => self.shot = Some((projectile, 0)),
+                Some(Event::Moved { entity, from, to }) => {
+                    self.motions.retain(|m| m.entity != entity);
+                    // Anything further than a step, like a teleport, just
+                    // appears in its new place
+                    if (to.x - from.x).abs() + (to.y - from.y).abs() == 1 {
+                        self.motions.push(Motion { entity, from, to, frame: 0 });
+                    }
+                },
+                Some(Event::Damaged { entity, pos, amount }) => {
+                    self.flashes.retain(|f| f.0 != entity);
+                    self.flashes.push((entity, FLASH_FRAMES));
+                    let colour = if entity == self.hero {
+                        Colour::new(230, 60, 60, 255)
+                    } else {
+                        Colour::new(240, 220, 80, 255)
+                    };
+                    self.numbers.push(Number { pos, text: amount.to_string(), colour, frame: 0 });
+                },
+                None => break
+            }
+        }
+    }
+
+    // How far from its cell an entity should be drawn. It stays where it was
+    // until its move comes up, then slides the rest of the way
+    pub fn offset(&self, e: Entity, pos: Vec2i) -> Vec2f {
+        let waiting = self.pending.iter().find_map(|event| match event {
+            Event::Moved { entity, from, .. } if *entity == e => Some(*from),
+            _ => None
+        });
+        if let Some(from) = waiting {
+            return (from - pos).to_f32();
+        }
+
+        match self.motions.iter().find(|m| m.entity == e && m.to == pos) {
+            Some(m) => {
+                let left = 1.0 - m.frame as f32 / MOVE_FRAMES as f32;
+                let d = (m.from - m.to).to_f32();
+                Vec2f::new(d.x*left, d.y*left)
+            },
+            None => Vec2f::new(0.0, 0.0)
+        }
+    }
+
+    pub fn is_flashing(&self, e: Entity) -> bool {
+        self.flashes.iter().any(|f| f.0 == e)
+    }
+
+    // The shot in flight, the trails behind it and damage numbers, wherever
+    // the hero can see them
+    pub fn draw(&self, renderer: &mut dyn Renderer, camera: &Camera, level: &Level) {
+        let shown = |pos: Vec2i| camera.in_view(pos) && level.is_visible(pos);
+
+        for &(pos, colour, left) in self.trails.iter().filter(|t| shown(t.0)) {
+            let fade = |c: u8| (c as usize*left / TRAIL_FRAMES) as u8;
+            renderer.draw_marker(camera.to_view(pos), Colour::new(fade(colour.r), fade(colour.g), fade(colour.b), 255));
+        }
+
+        if let Some((projectile, frame)) = self.shot.as_ref() {
+            if let Some(&pos) = projectile.path.get(*frame / FRAMES_PER_TILE).filter(|&&p| camera.in_view(p)) {
+                renderer.draw_marker(camera.to_view(pos), projectile.colour);
+            }
+        }
+
+        for number in self.numbers.iter().filter(|n| shown(n.pos)) {
+            let rise = NUMBER_RISE*number.frame as f32 / NUMBER_FRAMES as f32;
+            let pos = camera.to_view(number.pos).to_f32() - Vec2f::new(0.0, rise);
+            renderer.draw_label(pos, &number.text, number.colour);
+        }
+    }
+}
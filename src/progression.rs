@@ -0,0 +1,116 @@
+extern crate rand;
+use rand::seq::SliceRandom;
+
+use crate::ecs::Entity;
+use crate::stats::{Modifiers, NO_MODIFIERS};
+use crate::world::World;
+
+// Experience needed to go from the given level to the next
+pub fn experience_to_next(level: u32) -> u32 {
+    20 * level
+}
+
+// Every level grants these on top of the chosen perk
+const HEALTH_PER_LEVEL: u32 = 5;
+const ATTACK_PER_LEVEL: u32 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Perk {
+    Toughness,
+    Brawler,
+    Evasion,
+    Quickness,
+    Farsight,
+    FireWard
+}
+
+impl Perk {
+    pub const ALL: [Perk; 6] = [Perk::Toughness, Perk::Brawler, Perk::Evasion, Perk::Quickness, Perk::Farsight, Perk::FireWard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Perk::Toughness => "Toughness",
+            Perk::Brawler => "Brawler",
+            Perk::Evasion => "Evasion",
+            Perk::Quickness => "Quickness",
+            Perk::Farsight => "Farsight",
+            Perk::FireWard => "Fire ward"
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Perk::Toughness => "+10 maximum health",
+            Perk::Brawler => "+2 attack",
+            Perk::Evasion => "+2 defence",
+            Perk::Quickness => "+1 speed",
+            Perk::Farsight => "+2 light radius",
+            Perk::FireWard => "+25% fire resistance"
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        match self {
+            Perk::Toughness => NO_MODIFIERS,
+            Perk::Brawler => Modifiers { attack: 2, ..NO_MODIFIERS },
+            Perk::Evasion => Modifiers { defence: 2, ..NO_MODIFIERS },
+            Perk::Quickness => Modifiers { speed: 1, ..NO_MODIFIERS },
+            Perk::Farsight => Modifiers { light_radius: 2, ..NO_MODIFIERS },
+            Perk::FireWard => Modifiers { fire_resistance: 25, ..NO_MODIFIERS }
+        }
+    }
+}
+
+// Gives experience and applies any level ups it leads to
+pub fn award_experience(world: &mut World, e: Entity, amount: u32) -> Vec<String> {
+    let mut levels = 0;
+    let new_level = match world.progression.get_mut(e) {
+        Some(progression) => {
+            progression.experience += amount;
+            while progression.experience >= experience_to_next(progression.level) {
+                progression.experience -= experience_to_next(progression.level);
+                progression.level += 1;
+                progression.pending_perks += 1;
+                levels += 1;
+            }
+            progression.level
+        },
+        None => return Vec::new()
+    };
+
+    if let Some(stats) = world.stats.get_mut(e) {
+        stats.max_health += HEALTH_PER_LEVEL * levels;
+        stats.health += HEALTH_PER_LEVEL * levels;
+        stats.attack += ATTACK_PER_LEVEL * levels;
+    }
+
+    if levels > 0 {
+        vec![format!("Welcome to level {}!", new_level)]
+    } else {
+        Vec::new()
+    }
+}
+
+// A few different perks to pick from
+pub fn roll_perk_choices(world: &mut World, count: usize) -> Vec<Perk> {
+    let mut perks = Perk::ALL.to_vec();
+    perks.shuffle(&mut world.rng);
+    perks.truncate(count);
+    perks
+}
+
+pub fn choose_perk(world: &mut World, e: Entity, perk: Perk) {
+    if let Some(progression) = world.progression.get_mut(e) {
+        if progression.pending_perks == 0 {
+            return;
+        }
+        progression.pending_perks -= 1;
+        progression.perks.push(perk);
+    }
+    if perk == Perk::Toughness {
+        if let Some(stats) = world.stats.get_mut(e) {
+            stats.max_health += 10;
+            stats.health += 10;
+        }
+    }
+}
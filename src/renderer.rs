@@ -0,0 +1,56 @@
+use crate::game::Colour;
+use crate::math::{Vec2f, Vec2i};
+use crate::minimap::MapImage;
+
+// A map tile as the game sees it. Backends that only draw colours or
+// characters can ignore the rest
+pub struct TileSprite {
+    // What the tile looks like, so secret doors are "rock"
+    pub name: &'static str,
+    pub glyph: char,
+    // Already dimmed when the tile is out of sight
+    pub colour: Colour,
+    pub lit: bool,
+    // Solid neighbours: 1 north, 2 east, 4 south, 8 west
+    pub connections: u8
+}
+
+// Everything the game needs to put itself on screen. Positions are in cells,
+// it's up to each backend how big a cell is. Tiles, glyphs, outlines and
+// markers are map cells in the camera's view, drawn zoom cells wide; text and
+// panels are always in screen cells
+pub trait Renderer {
+    fn clear(&mut self, colour: Colour);
+
+    // The whole screen in cells
+    fn size(&self) -> Vec2i;
+
+    fn set_zoom(&mut self, zoom: i32);
+
+    // A map tile filling its cell
+    fn draw_tile(&mut self, pos: Vec2i, tile: &TileSprite);
+
+    // An actor or item, the name being what the hero knows it as. It can be
+    // part way between cells while it moves
+    fn draw_glyph(&mut self, pos: Vec2f, name: &str, glyph: char, colour: Colour);
+
+    // A little text over the map centred on a map cell, for damage numbers
+    fn draw_label(&mut self, pos: Vec2f, text: &str, colour: Colour);
+
+    // One character per cell, running to the right
+    fn draw_text(&mut self, pos: Vec2i, text: &str, colour: Colour);
+
+    // A solid block of cells for panels to sit on
+    fn fill_cells(&mut self, pos: Vec2i, size: Vec2i, colour: Colour);
+
+    // A box around a cell, for cursors and highlights
+    fn draw_outline(&mut self, pos: Vec2i, colour: Colour);
+
+    // A small mark in the middle of a cell, for paths and projectiles
+    fn draw_marker(&mut self, pos: Vec2i, colour: Colour);
+
+    // The whole level shrunk to fit a block of screen cells
+    fn draw_map(&mut self, pos: Vec2i, size: Vec2i, map: &MapImage);
+
+    fn present(&mut self);
+}
@@ -0,0 +1,262 @@
+extern crate sdl2;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use std::path::Path;
+
+use crate::font;
+use crate::game::Colour;
+use crate::math::{Vec2f, Vec2i};
+use crate::minimap::MapImage;
+use crate::renderer::{Renderer, TileSprite};
+use crate::tileset::{SpriteRef, Tileset};
+
+// Size of a cell in pixels at a UI scale of one on an ordinary display
+const CELL_SIZE: i32 = 16;
+
+// Cells shrink to keep at least this many on screen, down to a minimum size
+const MIN_COLUMNS: i32 = 40;
+const MIN_ROWS: i32 = 25;
+const MIN_CELL_SIZE: i32 = 8;
+
+// Draws cells as coloured squares on an SDL window, or as sprites once a
+// tileset is loaded. The grid of cells is centred in the window with the
+// leftover pixels as a border around it
+pub struct SdlRenderer<'a> {
+    canvas: Canvas<Window>,
+    tiles: Option<(Tileset, Texture<'a>)>,
+    zoom: i32,
+    ui_scale: f32,
+    // Pixels per cell and the top left of the grid, both in drawable pixels
+    // which high DPI displays have more of than window coordinates
+    cell_size: i32,
+    origin: Vec2i,
+    cells: Vec2i,
+    dpi_scale: f32
+}
+
+impl<'a> SdlRenderer<'a> {
+    pub fn new(canvas: Canvas<Window>) -> SdlRenderer<'a> {
+        let mut renderer = SdlRenderer {
+            canvas,
+            tiles: None,
+            zoom: 1,
+            ui_scale: 1.0,
+            cell_size: CELL_SIZE,
+            origin: Vec2i::new(0, 0),
+            cells: Vec2i::new(0, 0),
+            dpi_scale: 1.0
+        };
+        renderer.resize();
+        renderer
+    }
+
+    // Makes everything bigger or smaller, on top of any DPI scaling
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.max(0.25);
+        self.resize();
+    }
+
+    // Fits the grid to the window again, for after it changes size or moves
+    // to a display with a different DPI
+    pub fn resize(&mut self) {
+        let (width, height) = self.canvas.output_size().unwrap_or((0, 0));
+        let (width, height) = (width as i32, height as i32);
+        let window_width = self.canvas.window().size().0.max(1);
+        self.dpi_scale = width as f32 / window_width as f32;
+
+        let wanted = (CELL_SIZE as f32*self.ui_scale*self.dpi_scale).round() as i32;
+        let fitting = (width / MIN_COLUMNS).min(height / MIN_ROWS);
+        self.cell_size = wanted.min(fitting).max(MIN_CELL_SIZE);
+        self.cells = Vec2i::new(width / self.cell_size, height / self.cell_size);
+        self.origin = Vec2i::new((width - self.cells.x*self.cell_size) / 2, (height - self.cells.y*self.cell_size) / 2);
+    }
+
+    // The screen cell under a point in window coordinates, as mouse events
+    // give them
+    pub fn cell_at(&self, x: i32, y: i32) -> Vec2i {
+        let px = (x as f32*self.dpi_scale) as i32 - self.origin.x;
+        let py = (y as f32*self.dpi_scale) as i32 - self.origin.y;
+        Vec2i::new(px.div_euclid(self.cell_size), py.div_euclid(self.cell_size))
+    }
+
+    // Anything without a sprite in the tileset is still drawn as a square
+    pub fn load_tileset(&mut self, path: &Path, creator: &'a TextureCreator<WindowContext>) -> Result<(), String> {
+        let tileset = Tileset::load(path)?;
+        let (pixels, width, height) = tileset.load_pixels()?;
+        // ABGR8888 is R, G, B, A in memory on little endian machines
+        let mut texture = creator.create_texture_static(PixelFormatEnum::ABGR8888, width, height)
+            .map_err(|e| e.to_string())?;
+        texture.update(None, &pixels, width as usize * 4).map_err(|e| e.to_string())?;
+        texture.set_blend_mode(BlendMode::Blend);
+        self.tiles = Some((tileset, texture));
+        Ok(())
+    }
+
+    // Copies a sprite from the sheet into a whole cell, multiplied by the
+    // colour if it is tinted or else by the brightness. Returns false if
+    // there is no sprite to draw
+    fn draw_sprite(&mut self, pos: Vec2f, sprite: Option<SpriteRef>, colour: Colour, brightness: u8) -> bool {
+        let dest = self.map_rect(pos, 0);
+        let (tileset, texture, sprite) = match (self.tiles.as_mut(), sprite) {
+            (Some((tileset, texture)), Some(sprite)) => (tileset, texture, sprite),
+            _ => return false
+        };
+        let size = tileset.size;
+        let columns = (texture.query().width / size).max(1);
+        let source = Rect::new(((sprite.index % columns)*size) as i32, ((sprite.index / columns)*size) as i32, size, size);
+
+        if sprite.tint {
+            texture.set_color_mod(colour.r, colour.g, colour.b);
+        } else {
+            texture.set_color_mod(brightness, brightness, brightness);
+        }
+        let _result = self.canvas.copy(texture, source, dest);
+        true
+    }
+
+    fn set_colour(&mut self, colour: Colour) {
+        self.canvas.set_draw_color(Color::RGBA(colour.r, colour.g, colour.b, colour.a));
+    }
+
+    // Lays out bitmap font glyphs from a pixel position, each one advance
+    // pixels to the right of the last
+    fn draw_font(&mut self, corner: Vec2i, advance: i32, scale: i32, text: &str, colour: Colour) {
+        self.set_colour(colour);
+        for (i, c) in text.chars().enumerate() {
+            let x = corner.x + i as i32*advance;
+            let mut rects = Vec::new();
+            for (row, bits) in font::glyph(c).iter().enumerate() {
+                for col in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - col)) != 0 {
+                        rects.push(Rect::new(x + col*scale, corner.y + row as i32*scale, scale as u32, scale as u32));
+                    }
+                }
+            }
+            let _result = self.canvas.fill_rects(&rects);
+        }
+    }
+
+    // Top left pixel of a screen cell
+    fn to_pixels(&self, pos: Vec2i) -> Vec2i {
+        Vec2i::new(self.origin.x + pos.x*self.cell_size, self.origin.y + pos.y*self.cell_size)
+    }
+
+    // A square inset from the edges of a map cell, both growing with the
+    // zoom. Insets are in sixteenths of a cell, and the cell can be part way
+    // between two for things on the move
+    fn map_rect(&self, pos: Vec2f, inset: i32) -> Rect {
+        let cell = self.cell_size*self.zoom;
+        let inset = inset*cell / CELL_SIZE;
+        let x = self.origin.x + (pos.x*cell as f32).round() as i32;
+        let y = self.origin.y + (pos.y*cell as f32).round() as i32;
+        let size = (cell - inset*2).max(1) as u32;
+        Rect::new(x + inset, y + inset, size, size)
+    }
+}
+
+impl<'a> Renderer for SdlRenderer<'a> {
+    // The border around the grid stays black
+    fn clear(&mut self, colour: Colour) {
+        self.set_colour(Colour::new(0, 0, 0, 255));
+        self.canvas.clear();
+        let size = self.cells;
+        self.fill_cells(Vec2i::new(0, 0), size, colour);
+    }
+
+    fn size(&self) -> Vec2i {
+        self.cells
+    }
+
+    fn set_zoom(&mut self, zoom: i32) {
+        self.zoom = zoom.max(1);
+    }
+
+    // Remembered tiles are dimmed to half brightness, as with the colours
+    fn draw_tile(&mut self, pos: Vec2i, tile: &TileSprite) {
+        let sprite = self.tiles.as_ref().and_then(|(t, _)| t.sprite(tile.name, tile.glyph, tile.connections));
+        let brightness = if tile.lit { 255 } else { 128 };
+        if !self.draw_sprite(pos.to_f32(), sprite, tile.colour, brightness) {
+            self.set_colour(tile.colour);
+            let _result = self.canvas.fill_rect(self.map_rect(pos.to_f32(), 1));
+        }
+    }
+
+    // Without a sprite, actors and items are drawn as squares
+    fn draw_glyph(&mut self, pos: Vec2f, name: &str, glyph: char, colour: Colour) {
+        let sprite = self.tiles.as_ref().and_then(|(t, _)| t.sprite(name, glyph, 0));
+        if !self.draw_sprite(pos, sprite, colour, 255) {
+            self.set_colour(colour);
+            let _result = self.canvas.fill_rect(self.map_rect(pos, 1));
+        }
+    }
+
+    // Glyphs from the bitmap font are scaled up in whole pixels to suit the
+    // cell size, double size for 16 pixel cells
+    fn draw_text(&mut self, pos: Vec2i, text: &str, colour: Colour) {
+        let scale = (self.cell_size / 8).max(1);
+        let margin = Vec2i::new((self.cell_size - font::GLYPH_WIDTH*scale) / 2, (self.cell_size - 7*scale) / 2);
+        let corner = self.to_pixels(pos) + margin;
+        self.draw_font(corner, self.cell_size, scale, text, colour);
+    }
+
+    // Half the size of ordinary text, so a few digits fit over a map cell
+    fn draw_label(&mut self, pos: Vec2f, text: &str, colour: Colour) {
+        let cell = self.cell_size*self.zoom;
+        let scale = (cell / 16).max(1);
+        let advance = (font::GLYPH_WIDTH + 1)*scale;
+        let width = advance*text.chars().count() as i32;
+        let rect = self.map_rect(pos, 0);
+        let corner = Vec2i::new(rect.x() + (cell - width) / 2, rect.y() + (cell - 7*scale) / 2);
+        self.draw_font(corner, advance, scale, text, colour);
+    }
+
+    fn fill_cells(&mut self, pos: Vec2i, size: Vec2i, colour: Colour) {
+        self.set_colour(colour);
+        let corner = self.to_pixels(pos);
+        let _result = self.canvas.fill_rect(Rect::new(corner.x, corner.y,
+            (size.x*self.cell_size).max(0) as u32, (size.y*self.cell_size).max(0) as u32));
+    }
+
+    fn draw_outline(&mut self, pos: Vec2i, colour: Colour) {
+        self.set_colour(colour);
+        let _result = self.canvas.draw_rect(self.map_rect(pos.to_f32(), 0));
+    }
+
+    fn draw_marker(&mut self, pos: Vec2i, colour: Colour) {
+        self.set_colour(colour);
+        let _result = self.canvas.fill_rect(self.map_rect(pos.to_f32(), 5));
+    }
+
+    // Every map cell gets the same whole number of pixels, as many as fit,
+    // and marks are drawn a little bigger so they stand out
+    fn draw_map(&mut self, pos: Vec2i, size: Vec2i, map: &MapImage) {
+        if map.width == 0 || map.height == 0 {
+            return;
+        }
+        let (width, height) = (map.width as i32, map.height as i32);
+        let area = Vec2i::new(size.x*self.cell_size, size.y*self.cell_size);
+        let scale = (area.x / width).min(area.y / height).max(1);
+        let corner = self.to_pixels(pos) + Vec2i::new((area.x - scale*width) / 2, (area.y - scale*height) / 2);
+
+        for (i, tile) in map.tiles.iter().enumerate() {
+            if let Some(colour) = *tile {
+                let (x, y) = (i as i32 % width, i as i32 / width);
+                self.set_colour(colour);
+                let _result = self.canvas.fill_rect(Rect::new(corner.x + x*scale, corner.y + y*scale, scale as u32, scale as u32));
+            }
+        }
+
+        let mark_size = scale + 2;
+        for mark in map.marks.iter() {
+            self.set_colour(mark.colour);
+            let _result = self.canvas.fill_rect(Rect::new(corner.x + mark.pos.x*scale - 1, corner.y + mark.pos.y*scale - 1,
+                mark_size as u32, mark_size as u32));
+        }
+    }
+
+    fn present(&mut self) {
+        self.canvas.present();
+    }
+}
@@ -0,0 +1,189 @@
+use crate::game::{Level, Tile, TileType};
+use crate::math::{line, Vec2i};
+use crate::status::{Status, StatusKind};
+use crate::systems;
+use crate::world::World;
+
+// Cones spread this far either side of the aim, as the cosine of the angle
+const CONE_SPREAD: f32 = 0.85;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shape {
+    // Stops at the first actor
+    Bolt,
+    // Flies like a bolt then bursts
+    Ball,
+    // Spreads out from the caster
+    Cone,
+    // Passes through everyone until it hits a wall
+    Line
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TileChange {
+    Ignite,
+    Freeze
+}
+
+#[derive(Debug, Clone)]
+pub struct Spell {
+    pub name: String,
+    pub mana: u32,
+    pub shape: Shape,
+    pub range: i32,
+    pub radius: i32,
+    pub damage: u32,
+    pub status: Option<Status>,
+    pub tile: Option<TileChange>
+}
+
+impl Spell {
+    fn new(name: &str) -> Spell {
+        Spell {
+            name: name.to_string(),
+            mana: 0,
+            shape: Shape::Bolt,
+            range: 1,
+            radius: 0,
+            damage: 0,
+            status: None,
+            tile: None
+        }
+    }
+}
+
+// The spell definitions are built into the binary from the data folder
+pub fn load_spells() -> Vec<Spell> {
+    parse_spells(include_str!("../data/spells.txt"))
+}
+
+fn parse_spells(text: &str) -> Vec<Spell> {
+    let mut spells: Vec<Spell> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            spells.push(Spell::new(&line[1..line.len()-1]));
+            continue;
+        }
+
+        let (key, value) = match line.find('=') {
+            Some(i) => (line[..i].trim(), line[i+1..].trim()),
+            None => panic!("spells.txt line {}: expected key = value", n + 1)
+        };
+        let spell = match spells.last_mut() {
+            Some(spell) => spell,
+            None => panic!("spells.txt line {}: property before the first spell", n + 1)
+        };
+        let number = |v: &str| v.parse::<i32>().unwrap_or_else(|_| panic!("spells.txt line {}: bad number", n + 1));
+
+        match key {
+            "mana" => spell.mana = number(value) as u32,
+            "range" => spell.range = number(value),
+            "radius" => spell.radius = number(value),
+            "damage" => spell.damage = number(value) as u32,
+            "shape" => spell.shape = match value {
+                "bolt" => Shape::Bolt,
+                "ball" => Shape::Ball,
+                "cone" => Shape::Cone,
+                "line" => Shape::Line,
+                _ => panic!("spells.txt line {}: unknown shape {}", n + 1, value)
+            },
+            "status" => {
+                let mut parts = value.split_whitespace();
+                let kind = match parts.next() {
+                    Some("burning") => StatusKind::Burning,
+                    Some("frozen") => StatusKind::Frozen,
+                    Some("poisoned") => StatusKind::Poisoned,
+                    Some("slowed") => StatusKind::Slowed,
+                    _ => panic!("spells.txt line {}: unknown status {}", n + 1, value)
+                };
+                let turns = number(parts.next().unwrap_or("")) as u32;
+                spell.status = Some(Status { kind, turns });
+            },
+            "tile" => spell.tile = match value {
+                "ignite" => Some(TileChange::Ignite),
+                "freeze" => Some(TileChange::Freeze),
+                _ => panic!("spells.txt line {}: unknown tile change {}", n + 1, value)
+            },
+            _ => panic!("spells.txt line {}: unknown property {}", n + 1, key)
+        }
+    }
+    spells
+}
+
+pub fn find(world: &World, name: &str) -> Option<usize> {
+    world.spells.iter().position(|s| s.name == name)
+}
+
+// The path the spell flies along, for animating it, and every cell it
+// affects once it gets there
+pub fn shape_cells(world: &World, spell: &Spell, from: Vec2i, target: Vec2i) -> (Vec<Vec2i>, Vec<Vec2i>) {
+    match spell.shape {
+        Shape::Bolt => {
+            let (path, _) = systems::trace_projectile(world, from, target, spell.range);
+            let area = path.last().cloned().into_iter().collect();
+            (path, area)
+        },
+        Shape::Ball => {
+            let (path, _) = systems::trace_projectile(world, from, target, spell.range);
+            let centre = path.last().cloned().unwrap_or(from);
+            let area = cells_in_radius(world, centre, spell.radius, |_| true);
+            (path, area)
+        },
+        Shape::Cone => {
+            let aim = target - from;
+            let length = ((aim.x*aim.x + aim.y*aim.y) as f32).sqrt();
+            let area = cells_in_radius(world, from, spell.range, |offset| {
+                let distance = ((offset.x*offset.x + offset.y*offset.y) as f32).sqrt();
+                length > 0.0 && distance > 0.0 &&
+                    (offset.x*aim.x + offset.y*aim.y) as f32 / (distance*length) >= CONE_SPREAD
+            });
+            (Vec::new(), area)
+        },
+        Shape::Line => {
+            // Carry on past the target to the full range
+            let aim = target - from;
+            let far = from + aim * Vec2i::new(spell.range, spell.range);
+            let path: Vec<Vec2i> = line(from, far).into_iter().skip(1)
+                .take(spell.range.max(0) as usize)
+                .take_while(|&p| !world.level.blocks_sight(p))
+                .collect();
+            (path.clone(), path)
+        }
+    }
+}
+
+// Open cells within the radius that can be seen from the centre
+fn cells_in_radius<F: Fn(Vec2i) -> bool>(world: &World, centre: Vec2i, radius: i32, include: F) -> Vec<Vec2i> {
+    let mut cells = Vec::new();
+    for j in -radius..=radius {
+        for i in -radius..=radius {
+            let offset = Vec2i::new(i, j);
+            let pos = centre + offset;
+            if i*i + j*j <= radius*radius && include(offset) && !world.level.blocks_sight(pos) &&
+                systems::line_of_sight(world, centre, pos) {
+                cells.push(pos);
+            }
+        }
+    }
+    cells
+}
+
+// Fire sets the floor alight and melts ice, cold freezes water and puts out fires
+pub fn change_tile(level: &mut Level, pos: Vec2i, change: TileChange) {
+    if !level.in_bounds(pos) {
+        return;
+    }
+    let tile = &mut level[pos.y as usize][pos.x as usize];
+    let new_type = match (change, tile.tile_type) {
+        (TileChange::Ignite, TileType::_Floor) => TileType::_Fire,
+        (TileChange::Ignite, TileType::_Ice) => TileType::_Water,
+        (TileChange::Freeze, TileType::_Water) => TileType::_Ice,
+        (TileChange::Freeze, TileType::_Fire) => TileType::_Floor,
+        _ => return
+    };
+    *tile = Tile::new(new_type);
+}
@@ -0,0 +1,59 @@
+#[derive(Debug, Copy, Clone)]
+pub struct Stats {
+    pub health: u32,
+    pub max_health: u32,
+    pub attack: u32,
+    pub defence: u32
+}
+
+impl Stats {
+    pub fn new(health: u32, attack: u32, defence: u32) -> Stats {
+        Stats { health, max_health: health, attack, defence }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn take_damage(&mut self, amount: u32) {
+        self.health = self.health.saturating_sub(amount);
+    }
+}
+
+// Bonuses from equipment, added on top of an entity's base stats
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Modifiers {
+    pub attack: i32,
+    pub defence: i32,
+    pub speed: i32,
+    pub light_radius: i32,
+    // Percentage of fire damage ignored
+    pub fire_resistance: i32
+}
+
+pub const NO_MODIFIERS: Modifiers = Modifiers {
+    attack: 0,
+    defence: 0,
+    speed: 0,
+    light_radius: 0,
+    fire_resistance: 0
+};
+
+impl std::ops::Add for Modifiers {
+    type Output = Modifiers;
+
+    fn add(self, rhs: Modifiers) -> Modifiers {
+        Modifiers {
+            attack: self.attack + rhs.attack,
+            defence: self.defence + rhs.defence,
+            speed: self.speed + rhs.speed,
+            light_radius: self.light_radius + rhs.light_radius,
+            fire_resistance: self.fire_resistance + rhs.fire_resistance
+        }
+    }
+}
+
+// Applies a signed bonus without going below zero
+pub fn apply_modifier(base: u32, modifier: i32) -> u32 {
+    (base as i32 + modifier).max(0) as u32
+}
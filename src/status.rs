@@ -0,0 +1,120 @@
+use crate::components::Statuses;
+use crate::ecs::Entity;
+use crate::game::Colour;
+use crate::systems;
+use crate::world::World;
+
+// Damage each turn of burning does before resistance
+const BURN_DAMAGE: u32 = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StatusKind {
+    Burning,
+    Frozen,
+    Poisoned,
+    Slowed
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Burning => "Burning",
+            StatusKind::Frozen => "Frozen",
+            StatusKind::Poisoned => "Poisoned",
+            StatusKind::Slowed => "Slowed"
+        }
+    }
+
+    pub fn colour(&self) -> Colour {
+        match self {
+            StatusKind::Burning => Colour::new(230, 110, 30, 255),
+            StatusKind::Frozen => Colour::new(170, 220, 255, 255),
+            StatusKind::Poisoned => Colour::new(110, 200, 60, 255),
+            StatusKind::Slowed => Colour::new(150, 150, 200, 255)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub turns: u32
+}
+
+pub fn has(world: &World, e: Entity, kind: StatusKind) -> bool {
+    world.statuses.get(e).is_some_and(|s| s.has(kind))
+}
+
+// Starts or extends a status and describes it if it is new
+pub fn inflict(world: &mut World, e: Entity, status: Status) -> Vec<String> {
+    if !world.stats.contains(e) {
+        return Vec::new();
+    }
+    let is_new = !has(world, e, status.kind);
+    // Fire and ice cancel each other out
+    match status.kind {
+        StatusKind::Burning => { cure(world, e, StatusKind::Frozen); },
+        StatusKind::Frozen => { cure(world, e, StatusKind::Burning); },
+        _ => {}
+    }
+    match world.statuses.get_mut(e) {
+        Some(statuses) => statuses.add(status),
+        None => {
+            let mut statuses = Statuses::new();
+            statuses.add(status);
+            world.statuses.insert(e, statuses);
+        }
+    }
+
+    if !is_new {
+        return Vec::new();
+    }
+    let name = world.describe(e);
+    let is_hero = e == world.hero;
+    let text = match status.kind {
+        StatusKind::Burning => format!("{} on fire!", systems::subject_verb(&name, is_hero, "are", "is")),
+        StatusKind::Frozen => format!("{} frozen solid!", systems::subject_verb(&name, is_hero, "are", "is")),
+        StatusKind::Poisoned => format!("{} poisoned.", systems::subject_verb(&name, is_hero, "are", "is")),
+        StatusKind::Slowed => format!("{} down.", systems::subject_verb(&name, is_hero, "slow", "slows"))
+    };
+    vec![text]
+}
+
+pub fn cure(world: &mut World, e: Entity, kind: StatusKind) -> bool {
+    match world.statuses.get_mut(e) {
+        Some(statuses) => statuses.remove(kind),
+        None => false
+    }
+}
+
+// Applies ongoing damage and counts every status down by a turn
+pub fn tick(world: &mut World, e: Entity) -> Vec<String> {
+    let mut messages = Vec::new();
+    if has(world, e, StatusKind::Burning) {
+        let resistance = world.modifiers(e).fire_resistance.clamp(0, 100) as u32;
+        // Rounded up so that only full resistance stops the burn
+        let damage = (BURN_DAMAGE * (100 - resistance)).div_ceil(100);
+        if damage > 0 {
+            let name = world.describe(e);
+            messages.push(format!("{} for {} damage.",
+                systems::subject_verb(&name, e == world.hero, "burn", "burns"), damage));
+            if systems::damage_entity(world, e, damage) {
+                if e == world.hero {
+                    messages.push("You die...".to_string());
+                }
+                return messages;
+            }
+        }
+    }
+
+    let expired = match world.statuses.get_mut(e) {
+        Some(statuses) => statuses.tick(),
+        None => Vec::new()
+    };
+    if e == world.hero {
+        for kind in expired {
+            messages.push(format!("You are no longer {}.", kind.name().to_lowercase()));
+        }
+    }
+    messages
+}
@@ -0,0 +1,299 @@
+extern crate rand;
+use rand::Rng;
+
+use crate::components::Renderable;
+use crate::ecs::Entity;
+use crate::event::Event;
+use crate::game::Colour;
+use crate::game::TileType;
+use crate::item::Slot;
+use crate::math::{line, Vec2i};
+use crate::progression;
+use crate::status;
+use crate::status::{Status, StatusKind};
+use crate::world::World;
+
+// How far the hero can see without any help
+const BASE_LIGHT_RADIUS: i32 = 6;
+
+// How long stepping into fire or onto ice lasts
+const FIRE_TURNS: u32 = 3;
+const ICE_TURNS: u32 = 3;
+
+// One in this many turns restores a point of health
+const REGEN_CHANCE: u32 = 8;
+
+// Mana comes back quicker than health
+const MANA_REGEN_CHANCE: u32 = 3;
+
+// Movement: keeps positions and the occupancy grid in step
+
+pub fn move_entity(world: &mut World, e: Entity, pos: Vec2i) {
+    let old_pos = world.position(e);
+    if world.occupancy.get(old_pos) == Some(e) {
+        world.occupancy.move_occupant(old_pos, pos);
+    }
+    world.positions.insert(e, pos);
+    world.events.push(Event::Moved { entity: e, from: old_pos, to: pos });
+}
+
+pub fn swap_entities(world: &mut World, a: Entity, b: Entity) {
+    let pos_a = world.position(a);
+    let pos_b = world.position(b);
+    world.positions.insert(a, pos_b);
+    world.positions.insert(b, pos_a);
+    world.occupancy.add(pos_b, a);
+    world.occupancy.add(pos_a, b);
+    world.events.push(Event::Moved { entity: a, from: pos_a, to: pos_b });
+    world.events.push(Event::Moved { entity: b, from: pos_b, to: pos_a });
+}
+
+// Combat: resolves a melee attack and returns what happened
+
+pub fn melee_attack(world: &mut World, attacker: Entity, defender: Entity) -> Vec<String> {
+    let attack = world.attack(attacker);
+    let attacker_name = world.describe(attacker);
+    let is_hero = attacker == world.hero;
+
+    let (hit, mut messages) = resolve_attack(world, attacker, defender, attack, &attacker_name, is_hero);
+    if hit && world.is_alive(defender) {
+        if let Some(status) = world.breeds.get(attacker).and_then(|b| b.hit_status) {
+            messages.extend(status::inflict(world, defender, status));
+        }
+    }
+    messages
+}
+
+// Attacks with something the attacker shot or threw, described by the missile
+pub fn ranged_attack(world: &mut World, attacker: Entity, defender: Entity, attack: u32, missile: &str) -> Vec<String> {
+    resolve_attack(world, attacker, defender, attack, missile, false).1
+}
+
+// Rolls to hit and deals the damage. The subject is whoever or whatever lands
+// the blow, the attacker gets the experience for a kill either way
+fn resolve_attack(world: &mut World, attacker: Entity, defender: Entity, attack: u32,
+                  subject: &str, second_person: bool) -> (bool, Vec<String>) {
+    let defence = world.defence(defender);
+    let defender_name = world.describe(defender);
+
+    // Roll a d20 against the defender's armour class
+    if world.rng.gen_range(1, 21) + attack <= 10 + defence {
+        return (false, vec![format!("{} {}.", subject_verb(subject, second_person, "miss", "misses"), defender_name)]);
+    }
+
+    let damage = world.rng.gen_range(1, attack.max(1) + 1);
+    (true, deal_damage(world, attacker, defender, damage, subject, second_person))
+}
+
+// Damage that always lands, like a spell. Handles kills the same way as attacks
+pub fn deal_damage(world: &mut World, attacker: Entity, defender: Entity, damage: u32,
+                   subject: &str, second_person: bool) -> Vec<String> {
+    let defender_name = world.describe(defender);
+    let mut messages = vec![format!("{} {} for {} damage.",
+        subject_verb(subject, second_person, "hit", "hits"), defender_name, damage)];
+
+    // Look this up before the kill despawns the defender
+    let experience = world.breeds.get(defender).map_or(0, |b| b.experience);
+    if damage_entity(world, defender, damage) {
+        if defender == world.hero {
+            messages.push("You die...".to_string());
+        } else {
+            messages.push(format!("{} {}.", subject_verb(subject, second_person, "kill", "kills"), defender_name));
+            messages.extend(progression::award_experience(world, attacker, experience));
+        }
+    }
+    messages
+}
+
+// Returns true if the damage killed the entity
+pub fn damage_entity(world: &mut World, e: Entity, amount: u32) -> bool {
+    match world.stats.get_mut(e) {
+        Some(stats) => stats.take_damage(amount),
+        None => return false
+    }
+    let pos = world.position(e);
+    world.events.push(Event::Damaged { entity: e, pos, amount });
+    if let Some(ai) = world.ais.get_mut(e) {
+        ai.asleep = false;
+    }
+    if world.is_alive(e) {
+        return false;
+    }
+
+    // The hero's body stays on the map under the game over screen
+    if e == world.hero {
+        if let Some(r) = world.renderables.get_mut(e) {
+            r.colour = Colour::new(100, 0, 0, 255);
+        }
+    } else {
+        world.despawn(e);
+    }
+    true
+}
+
+// Builds "You hit" or "The goblin hits" depending on who is acting
+pub fn subject_verb(name: &str, is_hero: bool, second_person: &str, third_person: &str) -> String {
+    let verb = if is_hero { second_person } else { third_person };
+    format!("{} {}", capitalise(name), verb)
+}
+
+pub fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}", first.to_uppercase(), chars.as_str()),
+        None => String::new()
+    }
+}
+
+pub fn light_radius(world: &World, e: Entity) -> i32 {
+    (BASE_LIGHT_RADIUS + world.modifiers(e).light_radius).max(1)
+}
+
+// End of turn: hazards from the tile underneath, statuses wearing off
+// and natural healing
+
+pub fn end_turn(world: &mut World, e: Entity) -> Vec<String> {
+    let mut messages = Vec::new();
+    let pos = world.position(e);
+    if world.level.in_bounds(pos) {
+        match world.level[pos.y as usize][pos.x as usize].tile_type {
+            TileType::_Fire => {
+                if world.modifiers(e).fire_resistance >= 100 {
+                    let name = world.describe(e);
+                    messages.push(format!("{} unharmed by the flames.", subject_verb(&name, e == world.hero, "are", "is")));
+                } else {
+                    messages.extend(status::inflict(world, e, Status { kind: StatusKind::Burning, turns: FIRE_TURNS }));
+                }
+            },
+            TileType::_Ice => {
+                messages.extend(status::inflict(world, e, Status { kind: StatusKind::Slowed, turns: ICE_TURNS }));
+            },
+            TileType::_Water => {
+                let doused = status::cure(world, e, StatusKind::Burning);
+                if doused && e == world.hero {
+                    messages.push("The water puts out the flames.".to_string());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    messages.extend(status::tick(world, e));
+    if !world.is_alive(e) {
+        return messages;
+    }
+
+    // Poison stops the body from mending itself
+    if !status::has(world, e, StatusKind::Poisoned) && world.rng.gen_range(0, REGEN_CHANCE) == 0 {
+        if let Some(stats) = world.stats.get_mut(e) {
+            stats.health = (stats.health + 1).min(stats.max_health);
+        }
+    }
+    if world.rng.gen_range(0, MANA_REGEN_CHANCE) == 0 {
+        if let Some(mana) = world.mana.get_mut(e) {
+            mana.current = (mana.current + 1).min(mana.max);
+        }
+    }
+    messages
+}
+
+// Projectiles: fly along the line of fire until something gets in the way
+
+pub struct Projectile {
+    pub path: Vec<Vec2i>,
+    pub colour: Colour
+}
+
+// The tiles a projectile passes through on its way to the target and whoever
+// it hits first. It stops short of walls and never flies further than range
+pub fn trace_projectile(world: &World, from: Vec2i, to: Vec2i, range: i32) -> (Vec<Vec2i>, Option<Entity>) {
+    let mut path = Vec::new();
+    for pos in line(from, to).into_iter().skip(1).take(range.max(0) as usize) {
+        if world.level.blocks_sight(pos) {
+            break;
+        }
+        path.push(pos);
+        if let Some(e) = world.entity_at(pos) {
+            return (path, Some(e));
+        }
+    }
+    (path, None)
+}
+
+// The launcher the entity is wielding, if any
+pub fn ranged_weapon(world: &World, e: Entity) -> Option<Entity> {
+    let weapon = *world.equipment.get(e)?.slots.get(&Slot::Weapon)?;
+    if world.items.get(weapon)?.item_type.range > 0 {
+        Some(weapon)
+    } else {
+        None
+    }
+}
+
+// Visibility: the hero sees everything within their light radius that isn't
+// hidden behind a wall
+
+pub fn line_of_sight(world: &World, from: Vec2i, to: Vec2i) -> bool {
+    let points = line(from, to);
+    points.len() < 3 || points[1..points.len()-1].iter().all(|&p| !world.level.blocks_sight(p))
+}
+
+pub fn update_visibility(world: &mut World) {
+    let centre = world.position(world.hero);
+    let radius = light_radius(world, world.hero);
+    world.level.clear_visible();
+
+    for j in -radius..=radius {
+        for i in -radius..=radius {
+            let pos = centre + Vec2i::new(i, j);
+            if i*i + j*j <= radius*radius && world.level.in_bounds(pos) && line_of_sight(world, centre, pos) {
+                world.level.set_visible(pos);
+            }
+        }
+    }
+}
+
+// Rendering: everything visible in the order it should be drawn
+
+pub fn render_list(world: &World) -> Vec<(Entity, Vec2i, Renderable)> {
+    let mut list: Vec<(Entity, Vec2i, Renderable)> = world.renderables.iter()
+        .filter(|(e, _)| world.traps.get(*e).is_none_or(|t| !t.hidden))
+        .filter_map(|(e, r)| world.positions.get(e).map(|&pos| (e, pos, *r)))
+        .filter(|(_, pos, _)| world.level.is_visible(*pos))
+        .collect();
+    list.sort_by_key(|(_, _, r)| r.layer);
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapping_keeps_occupancy_in_step() {
+        let mut world = World::new();
+        let hero = world.hero;
+        let other = world.spawn();
+        let pos = world.find_free_spawn();
+        world.positions.insert(other, pos);
+        world.occupancy.add(pos, other);
+
+        let hero_pos = world.position(hero);
+        swap_entities(&mut world, hero, other);
+        assert_eq!(world.position(hero), pos);
+        assert_eq!(world.position(other), hero_pos);
+        assert_eq!(world.occupancy.get(pos), Some(hero));
+        assert_eq!(world.occupancy.get(hero_pos), Some(other));
+    }
+
+    #[test]
+    fn moving_frees_the_old_cell() {
+        let mut world = World::new();
+        let hero = world.hero;
+        let from = world.position(hero);
+        let to = world.find_free_spawn();
+        move_entity(&mut world, hero, to);
+        assert_eq!(world.occupancy.get(from), None);
+        assert_eq!(world.occupancy.get(to), Some(hero));
+    }
+}
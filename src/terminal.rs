@@ -0,0 +1,280 @@
+extern crate crossterm;
+use crossterm::{cursor, event, queue, style, terminal};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::Color;
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+use crate::game::{Colour, Game, Mode};
+use crate::input::Input;
+use crate::math::{Vec2f, Vec2i};
+use crate::minimap::MapImage;
+use crate::renderer::{Renderer, TileSprite};
+
+const BLACK: Colour = Colour { r: 0, g: 0, b: 0, a: 255 };
+
+#[derive(Copy, Clone, PartialEq)]
+struct Cell {
+    glyph: char,
+    fg: Colour,
+    bg: Colour
+}
+
+const BLANK: Cell = Cell { glyph: ' ', fg: BLACK, bg: BLACK };
+
+// Draws cells as characters in a terminal. The frame is built up in a
+// buffer and only the cells that changed since the last one are written out
+pub struct TerminalRenderer {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    shown: Vec<Cell>,
+    zoom: i32
+}
+
+impl TerminalRenderer {
+    pub fn new() -> TerminalRenderer {
+        TerminalRenderer { width: 0, height: 0, cells: Vec::new(), shown: Vec::new(), zoom: 1 }
+    }
+
+    fn cell_mut(&mut self, pos: Vec2i) -> Option<&mut Cell> {
+        if pos.x < 0 || pos.y < 0 || pos.x as usize >= self.width || pos.y as usize >= self.height {
+            return None;
+        }
+        self.cells.get_mut(pos.y as usize*self.width + pos.x as usize)
+    }
+
+    // The block of characters a map cell covers at the current zoom
+    fn map_cells(&self, pos: Vec2i) -> Vec<Vec2i> {
+        let zoom = self.zoom;
+        (0..zoom*zoom).map(|i| Vec2i::new(pos.x*zoom + i % zoom, pos.y*zoom + i / zoom)).collect()
+    }
+
+    // Starts again from a blank screen whenever the terminal changes size
+    fn fit_to_terminal(&mut self) {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let (width, height) = (width as usize, height as usize);
+        if width != self.width || height != self.height {
+            self.width = width;
+            self.height = height;
+            self.shown = Vec::new();
+            let _result = queue!(stdout(), terminal::Clear(terminal::ClearType::All));
+        }
+        self.cells = vec![BLANK; width*height];
+    }
+}
+
+// Black is the terminal's background, so very dark tiles are lifted to stay
+// visible against it
+fn lift(colour: Colour) -> Colour {
+    if colour.r.max(colour.g).max(colour.b) < 48 {
+        Colour::new(72, 72, 72, 255)
+    } else {
+        colour
+    }
+}
+
+fn to_color(colour: Colour) -> Color {
+    Color::Rgb { r: colour.r, g: colour.g, b: colour.b }
+}
+
+impl Renderer for TerminalRenderer {
+    // The backdrop grey the window uses would swamp a terminal, so it always
+    // clears to black
+    fn clear(&mut self, _colour: Colour) {
+        self.fit_to_terminal();
+    }
+
+    fn size(&self) -> Vec2i {
+        Vec2i::new(self.width as i32, self.height as i32)
+    }
+
+    fn set_zoom(&mut self, zoom: i32) {
+        self.zoom = zoom.max(1);
+    }
+
+    // Zoomed in, every character of a map cell repeats its glyph
+    fn draw_tile(&mut self, pos: Vec2i, tile: &TileSprite) {
+        for p in self.map_cells(pos) {
+            if let Some(cell) = self.cell_mut(p) {
+                *cell = Cell { glyph: tile.glyph, fg: lift(tile.colour), bg: BLACK };
+            }
+        }
+    }
+
+    // Characters can't sit between cells, so moving actors snap to the
+    // nearest one
+    fn draw_glyph(&mut self, pos: Vec2f, _name: &str, glyph: char, colour: Colour) {
+        let pos = Vec2i::new(pos.x.round() as i32, pos.y.round() as i32);
+        for p in self.map_cells(pos) {
+            if let Some(cell) = self.cell_mut(p) {
+                cell.glyph = glyph;
+                cell.fg = lift(colour);
+            }
+        }
+    }
+
+    fn draw_text(&mut self, pos: Vec2i, text: &str, colour: Colour) {
+        for (i, c) in text.chars().enumerate() {
+            if let Some(cell) = self.cell_mut(Vec2i::new(pos.x + i as i32, pos.y)) {
+                cell.glyph = c;
+                cell.fg = colour;
+            }
+        }
+    }
+
+    fn fill_cells(&mut self, pos: Vec2i, size: Vec2i, colour: Colour) {
+        for j in pos.y..pos.y + size.y {
+            for i in pos.x..pos.x + size.x {
+                if let Some(cell) = self.cell_mut(Vec2i::new(i, j)) {
+                    *cell = Cell { glyph: ' ', fg: colour, bg: colour };
+                }
+            }
+        }
+    }
+
+    fn draw_label(&mut self, pos: Vec2f, text: &str, colour: Colour) {
+        let zoom = self.zoom as f32;
+        let x = ((pos.x + 0.5)*zoom).floor() as i32 - text.len() as i32 / 2;
+        let y = (pos.y*zoom).round() as i32;
+        self.draw_text(Vec2i::new(x, y), text, colour);
+    }
+
+    // There is no room for a box inside a character, so the cell is
+    // highlighted instead
+    fn draw_outline(&mut self, pos: Vec2i, colour: Colour) {
+        for p in self.map_cells(pos) {
+            if let Some(cell) = self.cell_mut(p) {
+                cell.fg = BLACK;
+                cell.bg = colour;
+            }
+        }
+    }
+
+    fn draw_marker(&mut self, pos: Vec2i, colour: Colour) {
+        for p in self.map_cells(pos) {
+            if let Some(cell) = self.cell_mut(p) {
+                cell.glyph = '*';
+                cell.fg = colour;
+            }
+        }
+    }
+
+    // Each character stands for a block of map cells and takes the colour
+    // of the first explored one. The map is never stretched past one
+    // character per cell
+    fn draw_map(&mut self, pos: Vec2i, size: Vec2i, map: &MapImage) {
+        let (width, height) = (map.width as i32, map.height as i32);
+        let size = Vec2i::new(size.x.min(width), size.y.min(height));
+        if size.x <= 0 || size.y <= 0 {
+            return;
+        }
+
+        for sy in 0..size.y {
+            for sx in 0..size.x {
+                let colour = (sy*height / size.y..(sy + 1)*height / size.y)
+                    .flat_map(|y| (sx*width / size.x..(sx + 1)*width / size.x).map(move |x| (x, y)))
+                    .find_map(|(x, y)| map.tiles[(y*width + x) as usize]);
+                if let (Some(colour), Some(cell)) = (colour, self.cell_mut(pos + Vec2i::new(sx, sy))) {
+                    *cell = Cell { glyph: ' ', fg: colour, bg: colour };
+                }
+            }
+        }
+
+        for mark in map.marks.iter() {
+            let p = pos + Vec2i::new(mark.pos.x*size.x / width, mark.pos.y*size.y / height);
+            if let Some(cell) = self.cell_mut(p) {
+                cell.glyph = mark.glyph;
+                cell.fg = mark.colour;
+            }
+        }
+    }
+
+    fn present(&mut self) {
+        let mut out = stdout();
+        let mut current: Option<(Colour, Colour)> = None;
+        for (i, cell) in self.cells.iter().enumerate() {
+            if self.shown.get(i) == Some(cell) {
+                continue;
+            }
+            let _result = queue!(out, cursor::MoveTo((i % self.width) as u16, (i / self.width) as u16));
+            if current != Some((cell.fg, cell.bg)) {
+                let _result = queue!(out, style::SetForegroundColor(to_color(cell.fg)), style::SetBackgroundColor(to_color(cell.bg)));
+                current = Some((cell.fg, cell.bg));
+            }
+            let _result = queue!(out, style::Print(cell.glyph));
+        }
+        let _result = out.flush();
+        self.shown = self.cells.clone();
+    }
+}
+
+// Arrow keys always move, and so do the vi keys while nothing else could
+// want the letters
+fn key_input(game: &Game, key: KeyEvent) -> Option<Input> {
+    let vi_keys = game.mode == Mode::Playing || game.mode == Mode::Targeting;
+    match key.code {
+        KeyCode::Up => Some(Input::North),
+        KeyCode::Down => Some(Input::South),
+        KeyCode::Left => Some(Input::West),
+        KeyCode::Right => Some(Input::East),
+        KeyCode::Char('k') if vi_keys => Some(Input::North),
+        KeyCode::Char('j') if vi_keys => Some(Input::South),
+        KeyCode::Char('h') if vi_keys => Some(Input::West),
+        KeyCode::Char('l') if vi_keys => Some(Input::East),
+        KeyCode::Char(' ') | KeyCode::Char('.') => Some(Input::Wait),
+        KeyCode::Esc => Some(Input::Cancel),
+        KeyCode::Enter => Some(Input::Confirm),
+        KeyCode::Tab => Some(Input::Next),
+        KeyCode::Char('+') | KeyCode::Char('=') => Some(Input::Key('+')),
+        KeyCode::Char(c) if c == ',' || c == '-' || c.is_ascii_lowercase() => Some(Input::Key(c)),
+        _ => None
+    }
+}
+
+fn mouse_input(mouse: MouseEvent) -> Option<Input> {
+    let pos = Vec2i::new(mouse.column as i32, mouse.row as i32);
+    match mouse.kind {
+        MouseEventKind::Moved => Some(Input::Hover(pos)),
+        MouseEventKind::Down(MouseButton::Left) => Some(Input::Click(pos)),
+        _ => None
+    }
+}
+
+// Plays the game in the terminal until Ctrl-C or the player leaves the game
+// over screen
+pub fn run() {
+    let mut out = stdout();
+    let _result = terminal::enable_raw_mode();
+    let _result = queue!(out, terminal::EnterAlternateScreen, cursor::Hide, event::EnableMouseCapture);
+
+    let mut renderer = TerminalRenderer::new();
+    let mut game = Game::new();
+
+    'main: loop {
+        while event::poll(Duration::from_secs(0)).unwrap_or(false) {
+            let input = match event::read() {
+                Ok(Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers }))
+                    if modifiers.contains(KeyModifiers::CONTROL) => break 'main,
+                Ok(Event::Key(key)) => key_input(&game, key),
+                Ok(Event::Mouse(mouse)) => mouse_input(mouse),
+                _ => None
+            };
+            if let Some(input) = input {
+                game.handle_input(input);
+            }
+        }
+        if game.has_quit() {
+            break 'main;
+        }
+
+        game.tick();
+        game.draw(&mut renderer);
+
+        std::thread::sleep(Duration::new(0, 1000000000u32 / 60));
+    }
+
+    let _result = queue!(out, event::DisableMouseCapture, style::ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+    let _result = out.flush();
+    let _result = terminal::disable_raw_mode();
+}
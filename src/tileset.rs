@@ -0,0 +1,123 @@
+extern crate png;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+// A sprite sheet and which sprite to draw for each thing in the game. The
+// mapping file looks like
+//
+//   # Comments start with a hash
+//   image = tiles.png
+//   size = 16
+//   floor = 0
+//   wall = 16 autotile
+//   locked door = 4 tint
+//   goblin shaman = 40
+//   g = 41
+//
+// The image is found relative to the mapping file and holds square sprites
+// of `size` pixels, numbered left to right and then top to bottom. Names are
+// tile appearances or entity names, and a single character maps every
+// entity drawn with that glyph that has no sprite of its own.
+//
+// An autotiled sprite is followed by fifteen more, one for each way it can
+// join its neighbours: add 1 for north, 2 for east, 4 for south and 8 for
+// west. A tinted sprite is multiplied by the colour the game would have
+// drawn, which keeps locked doors matching their keys.
+pub struct Tileset {
+    pub image: PathBuf,
+    pub size: u32,
+    sprites: HashMap<String, Sprite>
+}
+
+#[derive(Copy, Clone)]
+struct Sprite {
+    index: u32,
+    autotile: bool,
+    tint: bool
+}
+
+// Where a sprite sits on the sheet, and whether to tint it
+#[derive(Copy, Clone)]
+pub struct SpriteRef {
+    pub index: u32,
+    pub tint: bool
+}
+
+impl Tileset {
+    pub fn load(path: &Path) -> Result<Tileset, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let name = path.display();
+
+        let mut image = None;
+        let mut size = 16;
+        let mut sprites = HashMap::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i+1..].trim()),
+                None => return Err(format!("{} line {}: expected key = value", name, n + 1))
+            };
+            let mut words = value.split_whitespace();
+            let number = words.next().and_then(|w| w.parse::<u32>().ok());
+
+            match key {
+                "image" => image = Some(dir.join(value)),
+                "size" => size = number.ok_or_else(|| format!("{} line {}: bad size", name, n + 1))?,
+                _ => {
+                    let index = number.ok_or_else(|| format!("{} line {}: bad sprite index", name, n + 1))?;
+                    let mut sprite = Sprite { index, autotile: false, tint: false };
+                    for word in words {
+                        match word {
+                            "autotile" => sprite.autotile = true,
+                            "tint" => sprite.tint = true,
+                            _ => return Err(format!("{} line {}: unknown option {}", name, n + 1, word))
+                        }
+                    }
+                    sprites.insert(key.to_lowercase(), sprite);
+                }
+            }
+        }
+
+        match image {
+            Some(image) if size > 0 => Ok(Tileset { image, size, sprites }),
+            Some(_) => Err(format!("{}: size must be more than zero", name)),
+            None => Err(format!("{}: no image given", name))
+        }
+    }
+
+    // The sprite for a name, or failing that for a glyph. Connections only
+    // matter for autotiled sprites
+    pub fn sprite(&self, name: &str, glyph: char, connections: u8) -> Option<SpriteRef> {
+        let sprite = self.sprites.get(&name.to_lowercase())
+            .or_else(|| self.sprites.get(&glyph.to_string()))?;
+        let offset = if sprite.autotile { connections as u32 & 15 } else { 0 };
+        Some(SpriteRef { index: sprite.index + offset, tint: sprite.tint })
+    }
+
+    // The sheet as 8 bit RGBA rows, along with its width and height
+    pub fn load_pixels(&self) -> Result<(Vec<u8>, u32, u32), String> {
+        let name = self.image.display();
+        let file = File::open(&self.image).map_err(|e| format!("Can't open {}: {}", name, e))?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder.read_info().map_err(|e| format!("Can't decode {}: {}", name, e))?;
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).map_err(|e| format!("Can't decode {}: {}", name, e))?;
+
+        let pixels = match info.color_type {
+            png::ColorType::RGBA => data,
+            png::ColorType::RGB => data.chunks(3).flat_map(|p| vec![p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => data.chunks(2).flat_map(|p| vec![p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => data.iter().flat_map(|&v| vec![v, v, v, 255]).collect(),
+            png::ColorType::Indexed => return Err(format!("Can't expand the palette in {}", name))
+        };
+        Ok((pixels, info.width, info.height))
+    }
+}
@@ -0,0 +1,146 @@
+extern crate rand;
+use rand::Rng;
+
+use crate::components::*;
+use crate::ecs::Entity;
+use crate::effect;
+use crate::effect::Effect;
+use crate::game::{Colour, Tile, TileType};
+use crate::math::Vec2i;
+use crate::status;
+use crate::status::{Status, StatusKind};
+use crate::systems;
+use crate::world::World;
+
+// Most damage a dart does
+const DART_DAMAGE: u32 = 4;
+
+// How far around the searcher to look, and the odds of spotting each thing
+const SEARCH_RADIUS: i32 = 2;
+const SEARCH_CHANCE: u32 = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TrapKind {
+    Dart,
+    Teleport,
+    Alarm,
+    Trapdoor
+}
+
+impl TrapKind {
+    pub const ALL: [TrapKind; 4] = [TrapKind::Dart, TrapKind::Teleport, TrapKind::Alarm, TrapKind::Trapdoor];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapKind::Dart => "Dart trap",
+            TrapKind::Teleport => "Teleport trap",
+            TrapKind::Alarm => "Alarm trap",
+            TrapKind::Trapdoor => "Trapdoor"
+        }
+    }
+
+    fn colour(&self) -> Colour {
+        match self {
+            TrapKind::Dart => Colour::new(200, 200, 200, 255),
+            TrapKind::Teleport => Colour::new(160, 90, 230, 255),
+            TrapKind::Alarm => Colour::new(230, 200, 60, 255),
+            TrapKind::Trapdoor => Colour::new(120, 70, 30, 255)
+        }
+    }
+}
+
+pub fn spawn(world: &mut World, kind: TrapKind, pos: Vec2i) -> Entity {
+    let e = world.spawn();
+    world.positions.insert(e, pos);
+    world.renderables.insert(e, Renderable { glyph: '^', colour: kind.colour(), layer: LAYER_TRAP });
+    world.names.insert(e, kind.name().to_string());
+    world.traps.insert(e, Trap { kind, hidden: true });
+    e
+}
+
+fn trap_at(world: &World, pos: Vec2i) -> Option<Entity> {
+    world.traps.iter()
+        .find(|(e, _)| world.positions.get(*e) == Some(&pos))
+        .map(|(e, _)| e)
+}
+
+// Springs any trap where the hero just stepped. Monsters know where the traps
+// are and never set them off
+pub fn trigger_at(world: &mut World, e: Entity, pos: Vec2i) -> Vec<String> {
+    if e != world.hero {
+        return Vec::new();
+    }
+    let (trap, kind) = match trap_at(world, pos) {
+        Some(trap) => match world.traps.get_mut(trap) {
+            Some(t) => {
+                t.hidden = false;
+                (trap, t.kind)
+            },
+            None => return Vec::new()
+        },
+        None => return Vec::new()
+    };
+
+    let mut messages = vec![format!("You set off {}!", world.describe(trap))];
+    match kind {
+        TrapKind::Dart => {
+            let damage = world.rng.gen_range(1, DART_DAMAGE + 1);
+            messages.extend(systems::deal_damage(world, e, e, damage, "the dart", false));
+            if world.is_alive(e) {
+                messages.extend(status::inflict(world, e, Status { kind: StatusKind::Poisoned, turns: 5 }));
+            }
+        },
+        TrapKind::Teleport => messages.extend(effect::apply(world, e, Effect::Teleport)),
+        TrapKind::Alarm => {
+            for (_, ai) in world.ais.iter_mut() {
+                ai.asleep = false;
+            }
+            messages.push("A loud alarm rings out!".to_string());
+        },
+        TrapKind::Trapdoor => {
+            world.descend();
+            messages.push(format!("You fall through to depth {}.", world.depth));
+        }
+    }
+    messages
+}
+
+// Looks around for hidden traps and secret doors
+pub fn search(world: &mut World, e: Entity) -> Vec<String> {
+    let centre = world.position(e);
+    let mut messages = Vec::new();
+
+    let hidden: Vec<Entity> = world.traps.iter()
+        .filter(|(_, t)| t.hidden)
+        .map(|(t, _)| t)
+        .filter(|&t| world.positions.get(t).is_some_and(|&p|
+            (p.x - centre.x).abs() <= SEARCH_RADIUS && (p.y - centre.y).abs() <= SEARCH_RADIUS))
+        .collect();
+    for trap in hidden {
+        if world.rng.gen_range(0, SEARCH_CHANCE) == 0 {
+            if let Some(t) = world.traps.get_mut(trap) {
+                t.hidden = false;
+            }
+            messages.push(format!("You find {}.", world.describe(trap)));
+        }
+    }
+
+    for j in -SEARCH_RADIUS..=SEARCH_RADIUS {
+        for i in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            let pos = centre + Vec2i::new(i, j);
+            if !world.level.in_bounds(pos) {
+                continue;
+            }
+            let tile = &mut world.level[pos.y as usize][pos.x as usize];
+            if tile.tile_type == TileType::_SecretDoor && world.rng.gen_range(0, SEARCH_CHANCE) == 0 {
+                *tile = Tile::new(TileType::_Door);
+                messages.push("You find a secret door.".to_string());
+            }
+        }
+    }
+
+    if messages.is_empty() {
+        messages.push("You find nothing.".to_string());
+    }
+    messages
+}
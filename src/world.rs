@@ -0,0 +1,356 @@
+extern crate rand;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::components::*;
+use crate::ecs::{Entity, Storage};
+use crate::event::Event;
+use crate::game::{Direction, Level, TileType};
+use crate::hero;
+use crate::item;
+use crate::math::Vec2i;
+use crate::item::ItemType;
+use crate::maze::{Lock, Maze};
+use crate::message::MessageLog;
+use crate::monster;
+use crate::monster::Breed;
+use crate::occupancy::Occupancy;
+use crate::spell;
+use crate::spell::Spell;
+use crate::stats::{apply_modifier, Modifiers, Stats, NO_MODIFIERS};
+use crate::status::StatusKind;
+use crate::trap;
+use crate::trap::TrapKind;
+
+// Everything an action is allowed to see and change. Entities are just ids,
+// their data lives in the component storages below
+pub struct World {
+    pub level: Level,
+    pub occupancy: Occupancy,
+    pub messages: MessageLog,
+    // Locked doors and the key that opens each one
+    pub locks: Vec<(Vec2i, &'static ItemType)>,
+    // What has happened since the game last looked, for it to animate
+    pub events: Vec<Event>,
+    pub rng: StdRng,
+    pub hero: Entity,
+    // How many floors down the hero is
+    pub depth: u32,
+    // Turns the hero has taken
+    pub turns: u32,
+    next_entity: usize,
+
+    // Every spell there is, loaded from the data folder
+    pub spells: Vec<Spell>,
+
+    // Unidentified item types are shown by their appearance
+    appearances: HashMap<&'static str, String>,
+    identified: HashSet<&'static str>,
+
+    pub positions: Storage<Vec2i>,
+    pub renderables: Storage<Renderable>,
+    pub names: Storage<String>,
+    pub stats: Storage<Stats>,
+    pub speeds: Storage<Speed>,
+    pub factions: Storage<Faction>,
+    pub ais: Storage<Ai>,
+    pub items: Storage<Item>,
+    pub inventories: Storage<Inventory>,
+    pub equipment: Storage<Equipment>,
+    pub breeds: Storage<&'static Breed>,
+    pub progression: Storage<Progression>,
+    pub statuses: Storage<Statuses>,
+    pub mana: Storage<Mana>,
+    pub spellbooks: Storage<Spellbook>,
+    pub traps: Storage<Trap>
+}
+
+impl World {
+    pub fn new() -> World {
+        let layout = Maze::new(49, 37);
+        let level: Level = layout.level;
+        let occupancy = Occupancy::new(level.width, level.height);
+        let mut rng = StdRng::from_entropy();
+        let appearances = item::random_appearances(&mut rng);
+        let mut world = World {
+            level,
+            occupancy,
+            messages: MessageLog::new(),
+            locks: Vec::new(),
+            events: Vec::new(),
+            rng,
+            hero: Entity(0),
+            depth: 1,
+            turns: 0,
+            next_entity: 0,
+            spells: spell::load_spells(),
+            appearances,
+            identified: HashSet::new(),
+            positions: Storage::new(),
+            renderables: Storage::new(),
+            names: Storage::new(),
+            stats: Storage::new(),
+            speeds: Storage::new(),
+            factions: Storage::new(),
+            ais: Storage::new(),
+            items: Storage::new(),
+            inventories: Storage::new(),
+            equipment: Storage::new(),
+            breeds: Storage::new(),
+            progression: Storage::new(),
+            statuses: Storage::new(),
+            mana: Storage::new(),
+            spellbooks: Storage::new(),
+            traps: Storage::new()
+        };
+
+        let start = Vec2i::new(layout.start.0, layout.start.1);
+        world.hero = hero::spawn(&mut world, start);
+        world.place_keys(&layout.locks);
+        world.populate();
+        world
+    }
+
+    // Leaves the matching key for every locked door, doors take on the colour
+    // of their key
+    fn place_keys(&mut self, locks: &[Lock]) {
+        self.locks.clear();
+        for (lock, &key_type) in locks.iter().zip(item::KEYS.iter()) {
+            let (x, y) = lock.door;
+            self.level[y][x].colour = key_type.colour;
+            self.locks.push((Vec2i::new(x as i32, y as i32), key_type));
+            item::spawn(self, key_type, Vec2i::new(lock.key.0 as i32, lock.key.1 as i32));
+        }
+    }
+
+    // Fills the current level with monsters, items and traps, a few more
+    // monsters on each floor down
+    fn populate(&mut self) {
+        for i in 0..9 + self.depth {
+            let breed = match i {
+                0 | 1 => &monster::RAT,
+                2 | 3 => &monster::SLIME,
+                4 => &monster::GOBLIN_SHAMAN,
+                _ => &monster::GOBLIN
+            };
+            let p = self.pack_spawn(breed).unwrap_or_else(|| self.find_free_spawn());
+            monster::spawn(self, breed, p);
+        }
+
+        for _ in 0..8 {
+            let (x, y) = Maze::find_spawn(&self.level);
+            let i = self.rng.gen_range(0, item::FLOOR_ITEMS.len());
+            item::spawn(self, item::FLOOR_ITEMS[i], Vec2i::new(x, y));
+        }
+
+        for (x, y) in Maze::find_trap_sites(&self.level, 6) {
+            let kind = TrapKind::ALL[self.rng.gen_range(0, TrapKind::ALL.len())];
+            trap::spawn(self, kind, Vec2i::new(x, y));
+        }
+    }
+
+    // Builds the next floor down. Only the hero and what they carry come along
+    pub fn descend(&mut self) {
+        let mut keep: HashSet<Entity> = HashSet::new();
+        keep.insert(self.hero);
+        if let Some(inventory) = self.inventories.get(self.hero) {
+            keep.extend(inventory.items.iter().cloned());
+        }
+        for id in 0..self.next_entity {
+            if !keep.contains(&Entity(id)) {
+                self.despawn(Entity(id));
+            }
+        }
+
+        self.depth += 1;
+        let layout = Maze::new(self.level.width, self.level.height);
+        self.level = layout.level;
+        self.occupancy = Occupancy::new(self.level.width, self.level.height);
+        self.events.clear();
+
+        let start = Vec2i::new(layout.start.0, layout.start.1);
+        self.positions.insert(self.hero, start);
+        self.occupancy.add(start, self.hero);
+        self.place_keys(&layout.locks);
+        self.populate();
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        let e = Entity(self.next_entity);
+        self.next_entity += 1;
+        e
+    }
+
+    pub fn despawn(&mut self, e: Entity) {
+        if let Some(pos) = self.positions.remove(e) {
+            if self.occupancy.get(pos) == Some(e) {
+                self.occupancy.remove(pos);
+            }
+        }
+        self.renderables.remove(e);
+        self.names.remove(e);
+        self.stats.remove(e);
+        self.speeds.remove(e);
+        self.factions.remove(e);
+        self.ais.remove(e);
+        self.items.remove(e);
+        self.inventories.remove(e);
+        self.equipment.remove(e);
+        self.breeds.remove(e);
+        self.progression.remove(e);
+        self.statuses.remove(e);
+        self.mana.remove(e);
+        self.spellbooks.remove(e);
+        self.traps.remove(e);
+    }
+
+    // Clustered breeds turn up in packs, on a free floor cell beside one
+    // already placed if there is room
+    fn pack_spawn(&self, breed: &Breed) -> Option<Vec2i> {
+        if !breed.clustered {
+            return None;
+        }
+        let (leader, _) = self.breeds.iter().find(|(_, b)| b.name == breed.name)?;
+        let pos = self.position(leader);
+        [Direction::North, Direction::South, Direction::East, Direction::West].iter()
+            .map(|d| pos + d.offset())
+            .find(|&p| self.level.in_bounds(p) && !self.occupancy.is_occupied(p)
+                && self.level[p.y as usize][p.x as usize].tile_type == TileType::_Floor)
+    }
+
+    pub fn find_free_spawn(&self) -> Vec2i {
+        loop {
+            let (x, y) = Maze::find_spawn(&self.level);
+            let pos = Vec2i::new(x, y);
+            if !self.occupancy.is_occupied(pos) {
+                return pos;
+            }
+        }
+    }
+
+    pub fn position(&self, e: Entity) -> Vec2i {
+        self.positions.get(e).cloned().unwrap_or(Vec2i::new(-1, -1))
+    }
+
+    pub fn is_alive(&self, e: Entity) -> bool {
+        self.stats.get(e).is_some_and(|s| s.is_alive())
+    }
+
+    pub fn is_hero_alive(&self) -> bool {
+        self.is_alive(self.hero)
+    }
+
+    // Sum of the bonuses from everything the entity has equipped and any perks
+    pub fn modifiers(&self, e: Entity) -> Modifiers {
+        let equipped = match self.equipment.get(e) {
+            Some(equipment) => equipment.slots.values()
+                .filter_map(|&i| self.items.get(i))
+                .fold(NO_MODIFIERS, |total, item| total + item.item_type.modifiers),
+            None => NO_MODIFIERS
+        };
+        match self.progression.get(e) {
+            Some(progression) => progression.perks.iter()
+                .fold(equipped, |total, perk| total + perk.modifiers()),
+            None => equipped
+        }
+    }
+
+    pub fn attack(&self, e: Entity) -> u32 {
+        apply_modifier(self.stats.get(e).map_or(0, |s| s.attack), self.modifiers(e).attack)
+    }
+
+    pub fn defence(&self, e: Entity) -> u32 {
+        apply_modifier(self.stats.get(e).map_or(0, |s| s.defence), self.modifiers(e).defence)
+    }
+
+    pub fn speed(&self, e: Entity) -> u32 {
+        let speed = apply_modifier(self.speeds.get(e).map_or(0, |s| s.speed), self.modifiers(e).speed);
+        let slowed = self.statuses.get(e).is_some_and(|s| s.has(StatusKind::Slowed));
+        if slowed { (speed / 2).max(1) } else { speed.max(1) }
+    }
+
+    pub fn entity_at(&self, pos: Vec2i) -> Option<Entity> {
+        self.occupancy.get(pos)
+    }
+
+    pub fn items_at(&self, pos: Vec2i) -> Vec<Entity> {
+        self.items.iter()
+            .filter(|(e, _)| self.positions.get(*e) == Some(&pos))
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    pub fn carried_weight(&self, e: Entity) -> u32 {
+        match self.inventories.get(e) {
+            Some(inventory) => inventory.items.iter()
+                .filter_map(|&i| self.items.get(i))
+                .map(|item| item.item_type.weight)
+                .sum(),
+            None => 0
+        }
+    }
+
+    pub fn is_hostile(&self, a: Entity, b: Entity) -> bool {
+        self.factions.get(a) != self.factions.get(b)
+    }
+
+    // "you" for the hero, "the goblin" for everyone else
+    pub fn describe(&self, e: Entity) -> String {
+        if e == self.hero {
+            return "you".to_string();
+        }
+        if self.items.contains(e) {
+            return format!("the {}", self.item_name(e).to_lowercase());
+        }
+        match self.names.get(e) {
+            Some(name) => format!("the {}", name.to_lowercase()),
+            None => "something".to_string()
+        }
+    }
+
+    pub fn item_name(&self, e: Entity) -> String {
+        let item_type = match self.items.get(e) {
+            Some(item) => item.item_type,
+            None => return self.names.get(e).cloned().unwrap_or_default()
+        };
+        if self.is_identified(e) {
+            return item_type.name.to_string();
+        }
+        self.appearances[item_type.name].clone()
+    }
+
+    pub fn is_identified(&self, e: Entity) -> bool {
+        match self.items.get(e) {
+            Some(item) => !self.appearances.contains_key(item.item_type.name) ||
+                self.identified.contains(item.item_type.name),
+            None => true
+        }
+    }
+
+    // Identifies every item of the same type
+    pub fn identify(&mut self, e: Entity) {
+        if let Some(item) = self.items.get(e) {
+            self.identified.insert(item.item_type.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rats_start_in_a_pack() {
+        let world = World::new();
+        let rats: Vec<Vec2i> = world.breeds.iter()
+            .filter(|(_, b)| b.name == monster::RAT.name)
+            .map(|(e, _)| world.position(e))
+            .collect();
+        assert_eq!(rats.len(), 2);
+        let d = rats[1] - rats[0];
+        assert_eq!(d.x.abs() + d.y.abs(), 1);
+    }
+}